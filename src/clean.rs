@@ -1,12 +1,38 @@
-use crate::{error, helpers};
+use crate::{error, helpers, ls::Details, metadata};
+use std::cmp::Reverse;
 use std::fs;
+use std::num::{IntErrorKind, ParseIntError};
 use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
 pub struct CleanOpts {
-    /// A regex to match against playground names. If not provided, all will be deleted.
+    /// A regex to match against playground names and descriptions. If not provided, all will be
+    /// deleted.
     #[structopt(long, short)]
     matches: Option<String>,
+    /// Only delete playgrounds with this tag. Can be given multiple times
+    #[structopt(long = "tag", number_of_values = 1)]
+    tags: Vec<String>,
+    /// Also delete pinned playgrounds, which are otherwise kept
+    #[structopt(long)]
+    include_pinned: bool,
+    /// Only delete playgrounds which haven't been modified or opened for this long, eg: `7d`. The
+    /// units are s, m, h, d and w
+    #[structopt(long, parse(try_from_str = parse_age))]
+    older_than: Option<u64>,
+    /// Only delete playgrounds which take up more than this much space, eg: `500M`. The units are
+    /// K, M, G and T
+    #[structopt(long, parse(try_from_str = helpers::parse_size))]
+    larger_than: Option<u64>,
+    /// Keep this many of the most recently modified or opened playgrounds
+    #[structopt(long)]
+    keep_recent: Option<usize>,
+    /// Print what would be deleted and how much space it takes up, without deleting anything
+    #[structopt(long)]
+    dry_run: bool,
+    /// Only delete the target directories of the playgrounds, keeping their sources
+    #[structopt(long)]
+    target_only: bool,
 }
 
 pub fn clean(opts: CleanOpts) -> error::Result<()> {
@@ -21,40 +47,177 @@ pub fn clean(opts: CleanOpts) -> error::Result<()> {
             let regex = regex::RegexBuilder::new(&matches)
                 .case_insensitive(true)
                 .build()
-                .map_err(|err| {
-                    error::Error::new(std::io::ErrorKind::InvalidInput, err)
-                })?;
+                .map_err(|err| error::Error::new(std::io::ErrorKind::InvalidInput, err))?;
             Some(regex)
         }
         None => None,
     };
 
+    let mut pinned = 0;
+    let mut playgrounds = Vec::new();
+
     // ignoring errors for now, maybe do something about it?
-    for entry in path.read_dir()?.flatten() {
-        let mut path = entry.path();
+    for name in helpers::list_playgrounds()? {
+        let details = Details::collect(name, false);
+        let description = details.metadata.description.as_deref().unwrap_or_default();
 
-        match (&regex, path.file_name().unwrap().to_str()) {
-            (None, _) => {}
-            (Some(regex), Some(path)) if regex.is_match(path) => {}
+        match &regex {
+            None => {}
+            Some(regex) if regex.is_match(&details.name) || regex.is_match(description) => {}
             _ => continue,
         }
 
-        path.push("Cargo.toml");
+        if !details.metadata.has_tags(&opts.tags) {
+            continue;
+        }
+
+        if details.metadata.pinned && !opts.include_pinned {
+            pinned += 1;
+            continue;
+        }
+
+        playgrounds.push(details);
+    }
+
+    if let Some(keep) = opts.keep_recent {
+        playgrounds.sort_by_key(|details| Reverse(details.last_used()));
+        playgrounds.drain(..keep.min(playgrounds.len()));
+    }
+
+    let now = metadata::now();
+    let mut total = 0;
+
+    for details in playgrounds {
+        if let Some(age) = opts.older_than {
+            // playgrounds which have never been used can't be too old
+            match details.last_used() {
+                Some(last_used) if now.saturating_sub(last_used) >= age => {}
+                _ => continue,
+            }
+        }
+
+        let mut path = path.join(&details.name);
+
+        if opts.target_only {
+            path.push("target");
+
+            if !path.exists() {
+                continue;
+            }
+        }
+
+        // measuring a target directory can take a while, so it is only done if needed
+        let size = if opts.dry_run || opts.larger_than.is_some() {
+            helpers::dir_size(&path)
+        } else {
+            0
+        };
+
+        if opts
+            .larger_than
+            .is_some_and(|larger_than| size <= larger_than)
+        {
+            continue;
+        }
 
-        if path.exists() {
-            path.pop();
+        if opts.dry_run {
+            let status = if opts.target_only {
+                "Would clean"
+            } else {
+                "Would delete"
+            };
+            helpers::print_status(
+                status,
+                &format!("{} ({})", details.name, helpers::format_size(size)),
+            );
+            println!();
 
-            if let Err(io_err) = fs::remove_dir_all(&path) {
-                let err = error::Error::new(
-                    io_err.kind(),
-                    format!("couldn't delete playground at {:?}: {}", path, io_err),
+            total += size;
+            continue;
+        }
+
+        if let Err(io_err) = fs::remove_dir_all(&path) {
+            let message = if opts.target_only {
+                format!(
+                    "couldn't clean the target directory of {}: {}",
+                    details.name, io_err
                 )
+            } else {
+                format!("couldn't delete playground at {:?}: {}", path, io_err)
+            };
+            let err = error::Error::new(io_err.kind(), message)
                 .with_help("check if the right directory is being cleaned");
 
-                eprintln!("{}", err);
-            }
+            eprintln!("{}", err);
         }
     }
 
+    if opts.dry_run {
+        helpers::print_status("Would free", &helpers::format_size(total));
+        println!();
+    }
+
+    if pinned > 0 {
+        helpers::print_status(
+            "Kept",
+            &format!(
+                "{} pinned playground(s), use --include-pinned to delete them",
+                pinned
+            ),
+        );
+        println!();
+    }
+
     Ok(())
 }
+
+/// Parses an age like `7d` or `12h` into seconds.
+fn parse_age(age: &str) -> Result<u64, String> {
+    let age = age.trim();
+    let split = age.find(|c: char| !c.is_ascii_digit()).unwrap_or(age.len());
+    let (number, unit) = age.split_at(split);
+
+    let number: u64 = number
+        .parse()
+        .map_err(|err: ParseIntError| match err.kind() {
+            IntErrorKind::PosOverflow => format!("'{}' is too long", age),
+            _ => format!("'{}' doesn't start with a number", age),
+        })?;
+
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 60 * 60 * 24,
+        "w" => 60 * 60 * 24 * 7,
+        _ => return Err(format!("unknown unit '{}', use s, m, h, d or w", unit)),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("'{}' is too long", age))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ages() {
+        assert_eq!(parse_age("7d"), Ok(7 * 24 * 60 * 60));
+        assert_eq!(parse_age("12h"), Ok(12 * 60 * 60));
+        assert_eq!(parse_age(" 30s "), Ok(30));
+        assert_eq!(parse_age("2w"), Ok(2 * 7 * 24 * 60 * 60));
+    }
+
+    #[test]
+    fn rejects_invalid_ages() {
+        // missing and unknown units
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("7y").is_err());
+        assert!(parse_age("d").is_err());
+        // overflowing
+        assert!(parse_age("99999999999999999999s").is_err());
+        assert!(parse_age("9999999999999999w").is_err());
+    }
+}
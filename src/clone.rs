@@ -0,0 +1,81 @@
+use crate::{error, helpers, manifest::Manifest, metadata, metadata::Metadata, new::names, open};
+use std::io;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct CloneOpts {
+    #[structopt(flatten)]
+    editor_opts: super::EditorOpts,
+    /// The name of the playground to clone
+    src: String,
+    /// The name of the copy. If not supplied, the name of the playground with a number after it is
+    /// used, eg: `foo-2`
+    dst: Option<String>,
+    /// Reuse the build artifacts of the playground by hard linking its target directory into the
+    /// copy, instead of leaving it out
+    #[structopt(long)]
+    reuse_target: bool,
+    /// Do not pass -w flag when opening GUI editor
+    #[structopt(long, requires("gui"))]
+    no_w: bool,
+    /// Indicates the editor is a gui editor
+    #[structopt(short, long)]
+    gui: bool,
+}
+
+/// Copies an existing playground to a new one and opens it, to branch off an experiment.
+pub fn clone(opts: CloneOpts) -> error::Result<()> {
+    let dir = helpers::get_dir();
+    let src = helpers::playground_path(&opts.src)?;
+
+    let name = match opts.dst {
+        Some(name) => name,
+        None => (2..)
+            .map(|i| format!("{}-{}", opts.src, i))
+            .find(|name| !dir.join(name).exists())
+            .unwrap_or_default(),
+    };
+
+    names::validate_name(&name)?;
+
+    let dst = dir.join(&name);
+
+    if dst.exists() {
+        return Err(error::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("playground '{}' already exists", name),
+        )
+        .with_help("choose a different name for the copy"));
+    }
+
+    helpers::print_status("Cloning", &format!("{} to {}", opts.src, name));
+    println!();
+
+    helpers::copy_dir(&src, &dst, &["target", open::OPEN_LOCK], None)?;
+
+    if opts.reuse_target && src.join("target").is_dir() {
+        helpers::link_dir(&src.join("target"), &dst.join("target"), &[])?;
+    }
+
+    let mut manifest = Manifest::open(dst.join("Cargo.toml"))?;
+    manifest.set_package_field("name", &name)?;
+    manifest.save()?;
+
+    // The copy keeps where the playground came from, but has a history of its own. It isn't pinned
+    // along with the original either, as it is an experiment which `clean` should be able to remove
+    Metadata::update(&dst, |metadata| {
+        metadata.created = Some(metadata::now());
+        metadata.pinned = false;
+        metadata.last_opened = None;
+        metadata.last_run = None;
+    })?;
+
+    open::open(open::OpenOpts {
+        name: Some(name),
+        tags: Vec::new(),
+        gui: opts.gui,
+        no_w: opts.no_w,
+        skip_check: true,
+        editor_opts: opts.editor_opts,
+    })
+}
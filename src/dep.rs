@@ -0,0 +1,491 @@
+use crate::error;
+use std::{fmt, io};
+use toml_edit::{Array, InlineTable, Item, Value};
+
+const DEP_HELP: &str =
+    "dependencies must be in the format '<name>[@<version>][+<features>][{<key>=<value>,...}]'
+       eg: 'rand', 'rand@0.8', 'serde@1+derive', 'tokio@1+full,-default'
+           'foo{git=https://github.com/foo/foo,branch=dev}'
+       supported keys are version, git, branch, tag, rev, path, package,
+       default-features and optional";
+
+/// A dependency as given on the command line, which can be written into a Cargo.toml.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dep {
+    pub name: String,
+    pub version: Option<String>,
+    pub features: Vec<String>,
+    pub default_features: bool,
+    pub git: Option<String>,
+    pub branch: Option<String>,
+    pub tag: Option<String>,
+    pub rev: Option<String>,
+    pub path: Option<String>,
+    pub package: Option<String>,
+    pub optional: bool,
+}
+
+impl Dep {
+    pub fn try_parse(dep: &str) -> error::Result<Self> {
+        let dep = dep.trim();
+
+        let name_end = dep.find(&['@', '=', '+', '{'][..]).unwrap_or(dep.len());
+        let name = dep[..name_end].trim();
+
+        if name.is_empty() || !name.chars().all(is_name_char) {
+            return Err(invalid(dep, "it does not start with a valid crate name"));
+        }
+
+        let mut parsed = Self {
+            name: name.to_owned(),
+            version: None,
+            features: Vec::new(),
+            default_features: true,
+            git: None,
+            branch: None,
+            tag: None,
+            rev: None,
+            path: None,
+            package: None,
+            optional: false,
+        };
+
+        let mut rest = &dep[name_end..];
+
+        // The legacy '<name>=<version>' format is just a different separator for the version
+        if let Some(r) = rest.strip_prefix('@').or_else(|| rest.strip_prefix('=')) {
+            let end = r.find(&['+', '{'][..]).unwrap_or(r.len());
+            let version = r[..end].trim();
+            if version.is_empty() {
+                return Err(invalid(dep, "the version is empty"));
+            }
+            parsed.version = Some(version.to_owned());
+            rest = &r[end..];
+        }
+
+        if let Some(r) = rest.strip_prefix('+') {
+            let end = r.find('{').unwrap_or(r.len());
+            for feature in r[..end].split(',').map(str::trim) {
+                match feature {
+                    "" => return Err(invalid(dep, "it has an empty feature")),
+                    "-default" => parsed.default_features = false,
+                    _ if feature.starts_with('-') => {
+                        return Err(
+                            invalid(dep, &format!("'{}' can't be disabled", &feature[1..]))
+                                .with_help(
+                                    "only the default features can be disabled, with '-default'",
+                                ),
+                        )
+                    }
+                    _ if feature.chars().all(is_feature_char) => {
+                        parsed.features.push(feature.to_owned())
+                    }
+                    _ => {
+                        return Err(invalid(
+                            dep,
+                            &format!("'{}' is not a valid feature", feature),
+                        ))
+                    }
+                }
+            }
+            rest = &r[end..];
+        }
+
+        if let Some(r) = rest.strip_prefix('{') {
+            let table = r
+                .strip_suffix('}')
+                .ok_or_else(|| invalid(dep, "the '{' is not closed by a '}' at the end"))?;
+
+            for entry in table.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+                let mut parts = entry.splitn(2, '=');
+                let key = parts.next().unwrap().trim();
+                let value = parts.next().map(unquote);
+
+                parsed.set(dep, key, value)?;
+            }
+
+            rest = "";
+        }
+
+        if !rest.trim().is_empty() {
+            return Err(invalid(dep, &format!("unexpected '{}'", rest.trim())));
+        }
+
+        parsed.validate(dep)?;
+
+        Ok(parsed)
+    }
+
+    /// The name of the package on crates.io (or in the git repository), which may be different
+    /// from the name it is imported with.
+    pub fn package_name(&self) -> &str {
+        self.package.as_deref().unwrap_or(&self.name)
+    }
+
+    /// Whether this is a plain registry dependency which only has a version.
+    fn is_simple(&self) -> bool {
+        self.features.is_empty()
+            && self.default_features
+            && self.git.is_none()
+            && self.path.is_none()
+            && self.package.is_none()
+            && !self.optional
+    }
+
+    /// Converts the dependency to the value of its entry in the `[dependencies]` table.
+    pub fn to_value(&self) -> Value {
+        if self.is_simple() {
+            if let Some(ref version) = self.version {
+                return version.as_str().into();
+            }
+        }
+
+        let mut table = InlineTable::new();
+
+        let strings = [
+            ("package", &self.package),
+            ("version", &self.version),
+            ("git", &self.git),
+            ("branch", &self.branch),
+            ("tag", &self.tag),
+            ("rev", &self.rev),
+            ("path", &self.path),
+        ];
+
+        for (key, value) in strings.iter() {
+            if let Some(value) = value {
+                table.insert(*key, value.as_str().into());
+            }
+        }
+
+        if !self.default_features {
+            table.insert("default-features", false.into());
+        }
+
+        if !self.features.is_empty() {
+            let features: Array = self.features.iter().map(String::as_str).collect();
+            table.insert("features", features.into());
+        }
+
+        if self.optional {
+            table.insert("optional", true.into());
+        }
+
+        table.fmt();
+        table.into()
+    }
+
+    /// Reads a dependency back from its entry in a `[dependencies]` table. Returns `None` if the
+    /// entry uses keys which aren't supported.
+    pub fn from_item(name: &str, item: &Item) -> Option<Self> {
+        let mut dep = Self {
+            name: name.to_owned(),
+            version: None,
+            features: Vec::new(),
+            default_features: true,
+            git: None,
+            branch: None,
+            tag: None,
+            rev: None,
+            path: None,
+            package: None,
+            optional: false,
+        };
+
+        if let Some(version) = item.as_str() {
+            dep.version = Some(version.to_owned());
+            return Some(dep);
+        }
+
+        for (key, value) in item.as_table_like()?.iter() {
+            match key {
+                "features" => {
+                    for feature in value.as_array()?.iter() {
+                        dep.features.push(feature.as_str()?.to_owned());
+                    }
+                }
+                "default-features" | "default_features" => {
+                    dep.default_features = value.as_bool()?
+                }
+                "optional" => dep.optional = value.as_bool()?,
+                _ => {
+                    let value = Some(value.as_str()?.to_owned());
+                    match key {
+                        "version" => dep.version = value,
+                        "git" => dep.git = value,
+                        "branch" => dep.branch = value,
+                        "tag" => dep.tag = value,
+                        "rev" => dep.rev = value,
+                        "path" => dep.path = value,
+                        "package" => dep.package = value,
+                        _ => return None,
+                    }
+                }
+            }
+        }
+
+        Some(dep)
+    }
+
+    /// Merges another specification of the same dependency into this one. The features are
+    /// combined, and a wildcard version is replaced by a concrete one. Any other differences are
+    /// conflicts and returned as an error.
+    pub fn merge(&mut self, other: &Dep) -> error::Result<()> {
+        fn merge_field(
+            name: &str,
+            field: &str,
+            this: &mut Option<String>,
+            other: &Option<String>,
+        ) -> error::Result<()> {
+            match (this.as_deref(), other.as_deref()) {
+                (_, None) => {}
+                (None, Some(_)) => *this = other.clone(),
+                (Some(a), Some(b)) if a == b => {}
+                (Some("*"), Some(_)) if field == "version" => *this = other.clone(),
+                (Some(_), Some("*")) if field == "version" => {}
+                (Some(a), Some(b)) => {
+                    return Err(conflict(
+                        name,
+                        &format!("it has {} '{}' instead of '{}'", field, b, a),
+                    ))
+                }
+            }
+
+            Ok(())
+        }
+
+        merge_field(&self.name, "package", &mut self.package, &other.package)?;
+        merge_field(&self.name, "version", &mut self.version, &other.version)?;
+        merge_field(&self.name, "git", &mut self.git, &other.git)?;
+        merge_field(&self.name, "branch", &mut self.branch, &other.branch)?;
+        merge_field(&self.name, "tag", &mut self.tag, &other.tag)?;
+        merge_field(&self.name, "rev", &mut self.rev, &other.rev)?;
+        merge_field(&self.name, "path", &mut self.path, &other.path)?;
+
+        if self.default_features != other.default_features {
+            return Err(conflict(
+                &self.name,
+                "only one of them disables the default features",
+            ));
+        }
+
+        if self.optional != other.optional {
+            return Err(conflict(&self.name, "only one of them is optional"));
+        }
+
+        for feature in &other.features {
+            if !self.features.contains(feature) {
+                self.features.push(feature.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set(&mut self, dep: &str, key: &str, value: Option<&str>) -> error::Result<()> {
+        let string_value = || match value {
+            Some(value) if !value.is_empty() => Ok(Some(value.to_owned())),
+            _ => Err(invalid(dep, &format!("'{}' requires a value", key))),
+        };
+
+        let bool_value = || match value {
+            None | Some("true") => Ok(true),
+            Some("false") => Ok(false),
+            Some(value) => Err(invalid(
+                dep,
+                &format!("'{}' must be true or false, not '{}'", key, value),
+            )),
+        };
+
+        match key {
+            "version" => self.version = string_value()?,
+            "git" => self.git = string_value()?,
+            "branch" => self.branch = string_value()?,
+            "tag" => self.tag = string_value()?,
+            "rev" => self.rev = string_value()?,
+            "path" => self.path = string_value()?,
+            "package" => self.package = string_value()?,
+            "default-features" | "default_features" => self.default_features = bool_value()?,
+            "optional" => self.optional = bool_value()?,
+            _ => return Err(invalid(dep, &format!("unknown key '{}'", key))),
+        }
+
+        Ok(())
+    }
+
+    fn validate(&mut self, dep: &str) -> error::Result<()> {
+        if self.git.is_some() && self.path.is_some() {
+            return Err(invalid(dep, "it cannot have both 'git' and 'path'"));
+        }
+
+        let git_refs = [&self.branch, &self.tag, &self.rev]
+            .iter()
+            .filter(|r| r.is_some())
+            .count();
+
+        if git_refs > 0 && self.git.is_none() {
+            return Err(invalid(
+                dep,
+                "'branch', 'tag' and 'rev' can only be used with 'git'",
+            ));
+        }
+
+        if git_refs > 1 {
+            return Err(invalid(
+                dep,
+                "only one of 'branch', 'tag' and 'rev' can be given",
+            ));
+        }
+
+        if let Some(package) = &self.package {
+            if !package.chars().all(is_name_char) {
+                return Err(invalid(dep, "'package' is not a valid crate name"));
+            }
+        }
+
+        if self.version.is_none() && self.git.is_none() && self.path.is_none() {
+            self.version = Some("*".into());
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Dep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.name, self.to_value())
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+fn is_feature_char(c: char) -> bool {
+    // features can refer to features of dependencies, eg: `serde/std` or `dep:serde`
+    is_name_char(c) || c == '/' || c == ':' || c == '?' || c == '.'
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+fn conflict(name: &str, reason: &str) -> error::Error {
+    error::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "dependency '{}' conflicts with the existing entry in Cargo.toml: {}",
+            name, reason
+        ),
+    )
+    .with_help(
+        "make sure the dependency is only given once, or that all of its specifications agree",
+    )
+}
+
+fn invalid(dep: &str, reason: &str) -> error::Error {
+    error::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("dependency '{}' is in an incorrect format: {}", dep, reason),
+    )
+    .with_help(DEP_HELP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_name_only() {
+        let dep = Dep::try_parse("rand").unwrap();
+        assert_eq!(dep.name, "rand");
+        assert_eq!(dep.version.as_deref(), Some("*"));
+        assert!(dep.features.is_empty());
+        assert!(dep.default_features);
+    }
+
+    #[test]
+    fn parses_version_and_legacy_version() {
+        assert_eq!(
+            Dep::try_parse("rand@0.8").unwrap().version.as_deref(),
+            Some("0.8")
+        );
+        assert_eq!(
+            Dep::try_parse("rand=0.8").unwrap().version.as_deref(),
+            Some("0.8")
+        );
+        assert!(Dep::try_parse("rand@").is_err());
+    }
+
+    #[test]
+    fn parses_features() {
+        let dep = Dep::try_parse("tokio@1+full,-default,serde/std").unwrap();
+        assert_eq!(dep.version.as_deref(), Some("1"));
+        assert_eq!(dep.features, ["full", "serde/std"]);
+        assert!(!dep.default_features);
+    }
+
+    #[test]
+    fn rejects_disabling_other_features() {
+        assert!(Dep::try_parse("serde+-std").is_err());
+        assert!(Dep::try_parse("serde+derive,-std").is_err());
+        assert!(Dep::try_parse("serde+derive,").is_err());
+    }
+
+    #[test]
+    fn parses_keys() {
+        let dep = Dep::try_parse("foo{git=https://github.com/foo/foo,branch=dev}").unwrap();
+        assert_eq!(dep.git.as_deref(), Some("https://github.com/foo/foo"));
+        assert_eq!(dep.branch.as_deref(), Some("dev"));
+        assert_eq!(dep.version, None);
+
+        let dep = Dep::try_parse("bar{path=\"../bar\",optional}").unwrap();
+        assert_eq!(dep.path.as_deref(), Some("../bar"));
+        assert!(dep.optional);
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(Dep::try_parse("foo{git=a,path=b}").is_err());
+        assert!(Dep::try_parse("foo{branch=dev}").is_err());
+        assert!(Dep::try_parse("foo{git=a,branch=dev,tag=v1}").is_err());
+        assert!(Dep::try_parse("foo{colour=red}").is_err());
+        assert!(Dep::try_parse("foo{optional=maybe}").is_err());
+        assert!(Dep::try_parse("foo{git=a").is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        assert!(Dep::try_parse("").is_err());
+        assert!(Dep::try_parse("@1").is_err());
+        assert!(Dep::try_parse("bad name").is_err());
+        assert!(Dep::try_parse("foo{package=bad name}").is_err());
+    }
+
+    #[test]
+    fn formats_simple_and_detailed_deps() {
+        assert_eq!(
+            Dep::try_parse("rand@0.8").unwrap().to_string(),
+            "rand = \"0.8\""
+        );
+        assert_eq!(
+            Dep::try_parse("serde@1+derive").unwrap().to_string(),
+            "serde = { version = \"1\", features = [\"derive\"] }"
+        );
+    }
+
+    #[test]
+    fn merges_features_and_wildcard_versions() {
+        let mut dep = Dep::try_parse("serde+derive").unwrap();
+        dep.merge(&Dep::try_parse("serde@1+rc").unwrap()).unwrap();
+        assert_eq!(dep.version.as_deref(), Some("1"));
+        assert_eq!(dep.features, ["derive", "rc"]);
+
+        assert!(dep.merge(&Dep::try_parse("serde@2").unwrap()).is_err());
+    }
+}
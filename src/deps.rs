@@ -0,0 +1,135 @@
+use crate::{dep::Dep, error, helpers, infer, manifest::Manifest, registry};
+use std::path::PathBuf;
+use std::{fs, io};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct AddOpts {
+    /// The name of the playground. If not supplied, it can be picked from a list
+    #[structopt(short, long)]
+    name: Option<String>,
+    /// Also offer to add the crates used by the code of the playground which aren't dependencies
+    /// yet. Only crates in the local registry cache are found
+    #[structopt(long)]
+    infer: bool,
+    /// The dependencies to add. They follow the same format as the dependencies of `new`
+    #[structopt(name = "dependencies", required_unless = "infer")]
+    deps: Vec<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct RemoveOpts {
+    /// The name of the playground. If not supplied, it can be picked from a list
+    #[structopt(short, long)]
+    name: Option<String>,
+    /// The dependencies to remove. They follow the same format as the dependencies of `new`,
+    /// though only the name is used
+    #[structopt(name = "dependencies", required = true)]
+    deps: Vec<String>,
+}
+
+pub fn add(opts: AddOpts) -> error::Result<()> {
+    let mut deps = opts
+        .deps
+        .iter()
+        .map(|dep| Dep::try_parse(dep))
+        .collect::<error::Result<Vec<_>>>()?;
+
+    let path = match get_manifest_path(opts.name)? {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    let mut manifest = Manifest::open(&path)?;
+
+    for dep in &deps {
+        manifest.add_dep(dep)?;
+    }
+
+    if opts.infer {
+        let playground = path.parent().unwrap_or(&path);
+
+        for dep in infer::offer(playground, &manifest)? {
+            manifest.add_dep(&dep)?;
+            deps.push(dep);
+        }
+    }
+
+    for dep in &deps {
+        helpers::print_status("Adding", &dep.to_string());
+        println!();
+    }
+
+    save_and_fetch(&manifest, &deps.iter().collect::<Vec<_>>())?;
+
+    Ok(())
+}
+
+pub fn remove(opts: RemoveOpts) -> error::Result<()> {
+    let deps = opts
+        .deps
+        .iter()
+        .map(|dep| Dep::try_parse(dep))
+        .collect::<error::Result<Vec<_>>>()?;
+
+    let path = match get_manifest_path(opts.name)? {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+
+    let mut manifest = Manifest::open(&path)?;
+
+    for dep in &deps {
+        if !manifest.remove_dep(&dep.name)? {
+            return Err(error::Error::new(
+                io::ErrorKind::NotFound,
+                format!("dependency '{}' is not present in the playground", dep.name),
+            )
+            .with_help("check the Cargo.toml of the playground for the dependencies it has"));
+        }
+    }
+
+    for dep in &deps {
+        helpers::print_status("Removing", &dep.name);
+        println!();
+    }
+
+    save_and_fetch(&manifest, &[])?;
+
+    Ok(())
+}
+
+/// Saves the edited manifest and fetches its dependencies. If they can't be fetched, the manifest
+/// and its lockfile are put back the way they were, so that a failed edit isn't kept.
+fn save_and_fetch(manifest: &Manifest, deps: &[&Dep]) -> error::Result<()> {
+    let lockfile = manifest.path().with_extension("lock");
+    let original = fs::read(manifest.path())?;
+    let original_lockfile = fs::read(&lockfile).ok();
+
+    manifest.save()?;
+
+    if let Err(err) = registry::fetch(manifest.path(), deps, false) {
+        fs::write(manifest.path(), original)?;
+        if let Some(original_lockfile) = original_lockfile {
+            fs::write(&lockfile, original_lockfile)?;
+        }
+
+        return Err(err);
+    }
+
+    Ok(())
+}
+
+/// Gets the path to the Cargo.toml of the given playground, asking the user to pick one if it
+/// isn't given.
+fn get_manifest_path(name: Option<String>) -> error::Result<Option<PathBuf>> {
+    let name = match name {
+        Some(name) => name,
+        None => match helpers::pick_playground("Pick a playground:", &[])? {
+            Some(name) => name,
+            None => return Ok(None),
+        },
+    };
+
+    Ok(Some(helpers::playground_path(&name)?.join("Cargo.toml")))
+}
@@ -1,4 +1,4 @@
-use std::{error, fmt, io};
+use std::{borrow::Cow, error, fmt, io};
 
 use crossterm::style::Stylize;
 
@@ -7,7 +7,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug)]
 pub struct Error {
     internal: io::Error,
-    help: Option<&'static str>,
+    help: Option<Cow<'static, str>>,
 }
 
 impl Error {
@@ -21,8 +21,8 @@ impl Error {
         }
     }
 
-    pub fn with_help(mut self, help: &'static str) -> Self {
-        self.help = Some(help);
+    pub fn with_help<H: Into<Cow<'static, str>>>(mut self, help: H) -> Self {
+        self.help = Some(help.into());
 
         self
     }
@@ -41,7 +41,7 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}: {}", "error".dark_red().bold(), self.internal)?;
 
-        if let Some(help) = self.help {
+        if let Some(ref help) = self.help {
             write!(f, "\n {}: {}", "help".dark_yellow().bold(), help,)?;
         }
 
@@ -0,0 +1,189 @@
+use crate::{
+    error, helpers, kind::Kind, manifest::Manifest, metadata, metadata::Metadata, new, new::names,
+    new::staging::Staging, registry, script::Script,
+};
+use regex::Regex;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct ExportOpts {
+    /// The name of the playground to export
+    playground: String,
+    /// The file to write the script to. By default it is written to stdout
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ImportOpts {
+    /// The cargo script to import, or `-` to read it from stdin
+    file: String,
+    /// The name of the playground. By default it is the name of the file
+    #[structopt(short, long)]
+    name: Option<String>,
+}
+
+/// Exports a playground as a single file cargo script, with its manifest embedded in a frontmatter
+/// block. Modules in other files are inlined into the script.
+pub fn export(opts: ExportOpts) -> error::Result<()> {
+    let playground = helpers::playground_path(&opts.playground)?;
+
+    let manifest = Manifest::open(playground.join("Cargo.toml"))?;
+    let entry = playground.join(Kind::of(&manifest)?.entry());
+
+    let source = fs::read_to_string(&entry).map_err(|err| {
+        error::Error::new(err.kind(), format!("couldn't read {:?}: {}", entry, err))
+    })?;
+
+    // The modules of the entry are next to it, as it is the root of the crate
+    let dir = entry.parent().unwrap_or(&playground);
+
+    let script = Script {
+        manifest: Some(manifest.to_script_manifest()),
+        code: inline_modules(&source, dir)?,
+    };
+
+    match opts.output {
+        Some(output) => {
+            fs::write(&output, script.render())?;
+
+            helpers::print_status(
+                "Exported",
+                &format!("{} to {}", opts.playground, output.display()),
+            );
+            println!();
+        }
+        None => print!("{}", script.render()),
+    }
+
+    Ok(())
+}
+
+/// Creates a playground from a cargo script, such as one made by `export`.
+pub fn import(opts: ImportOpts) -> error::Result<()> {
+    let source = if opts.file == "-" {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source).map_err(|err| {
+            error::Error::new(err.kind(), format!("couldn't read from stdin: {}", err))
+        })?;
+        source
+    } else {
+        fs::read_to_string(&opts.file).map_err(|err| {
+            error::Error::new(
+                err.kind(),
+                format!("couldn't read {:?}: {}", opts.file, err),
+            )
+        })?
+    };
+
+    let name = match opts.name {
+        Some(name) => name,
+        None if opts.file == "-" => {
+            return Err(error::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a name is required when importing from stdin",
+            )
+            .with_help("give the name of the playground with --name"))
+        }
+        None => Path::new(&opts.file)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+
+    names::validate_name(&name)?;
+
+    let script = Script::parse(&source);
+    let path = helpers::get_dir().join(&name);
+
+    if path.exists() {
+        return Err(error::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("playground '{}' already exists", name),
+        )
+        .with_help("give a different name with --name"));
+    }
+
+    helpers::print_status("Importing", &name);
+    println!();
+
+    // Like `new`, the playground is built in a staging directory so a failed import leaves nothing
+    // behind
+    let staging = Staging::new(&helpers::get_dir(), &name);
+    let staged = staging.path();
+
+    new::cargo_new(staged, &name, Kind::Bin, false)?;
+
+    let mut manifest = Manifest::open(staged.join("Cargo.toml"))?;
+
+    if let Some(ref fragment) = script.manifest {
+        manifest.merge(fragment)?;
+    }
+
+    // The targets of other kinds aren't part of the script, so they have to be set up again
+    let kind = Kind::of(&manifest)?;
+    if kind != Kind::Bin {
+        fs::remove_file(staged.join(Kind::Bin.entry()))?;
+        kind.scaffold(staged, &mut manifest)?;
+    }
+
+    fs::write(staged.join(kind.entry()), &script.code)?;
+    manifest.save()?;
+
+    registry::fetch(manifest.path(), &[], false)?;
+
+    let metadata = Metadata {
+        created: Some(metadata::now()),
+        from: Some(opts.file),
+        deps: manifest.dependency_names(),
+        ..Default::default()
+    };
+    metadata.save(staged)?;
+
+    staging.commit(&path)?;
+
+    Ok(())
+}
+
+/// Replaces the `mod foo;` declarations in a file with inline modules containing the files they
+/// refer to, recursively. `dir` is the directory the modules of the file are in.
+fn inline_modules(source: &str, dir: &Path) -> error::Result<String> {
+    let declaration = Regex::new(r"(?m)^([ \t]*(?:pub(?:\([^)]*\))?\s+)?mod\s+(\w+))\s*;").unwrap();
+
+    let mut inlined = String::with_capacity(source.len());
+    let mut last = 0;
+
+    for captures in declaration.captures_iter(source) {
+        let name = &captures[2];
+
+        let children = dir.join(name);
+        let mod_rs = children.join("mod.rs");
+        let file = if mod_rs.is_file() {
+            mod_rs
+        } else {
+            dir.join(format!("{}.rs", name))
+        };
+
+        let contents = fs::read_to_string(&file).map_err(|err| {
+            error::Error::new(
+                err.kind(),
+                format!("couldn't read module {} from {:?}: {}", name, file, err),
+            )
+        })?;
+
+        let whole = captures.get(0).unwrap();
+        inlined.push_str(&source[last..whole.start()]);
+        inlined.push_str(&captures[1]);
+        inlined.push_str(" {\n");
+        inlined.push_str(inline_modules(&contents, &children)?.trim_end());
+        inlined.push_str("\n}");
+        last = whole.end();
+    }
+
+    inlined.push_str(&source[last..]);
+
+    Ok(inlined)
+}
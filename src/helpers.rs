@@ -1,13 +1,14 @@
 use std::{
     convert::TryFrom,
-    env,
+    env, fs,
     io::{self, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{atomic, Arc},
     thread,
     time::Duration,
 };
 
+use crate::{error, metadata::Metadata};
 use crossterm::{
     cursor, event, execute, queue,
     style::{
@@ -24,6 +25,236 @@ pub fn get_dir() -> PathBuf {
         .unwrap_or_else(|| env::temp_dir().join("cargo-playground"))
 }
 
+/// Gets the path to an existing playground, or an error if there is no playground called `name`.
+pub fn playground_path(name: &str) -> error::Result<PathBuf> {
+    let path = get_dir().join(name);
+
+    if !path.join("Cargo.toml").is_file() {
+        return Err(error::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find playground '{}'", name),
+        )
+        .with_help("use `cargo playground ls` to list available playgrounds"));
+    }
+
+    Ok(path)
+}
+
+/// Gets the path to the directory in which named templates are stored.
+pub fn get_templates_dir() -> PathBuf {
+    env::var_os("CARGO_PLAYGROUND_TEMPLATES_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let config = env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| home::home_dir().map(|home| home.join(".config")))
+                .unwrap_or_else(env::temp_dir);
+
+            config.join("cargo-playground").join("templates")
+        })
+}
+
+/// Lists the names of all playgrounds, ie the cargo projects in the playgrounds directory.
+pub fn list_playgrounds() -> io::Result<Vec<String>> {
+    let path = get_dir();
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names: Vec<_> = path
+        .read_dir()?
+        .flatten()
+        .filter(|entry| entry.path().join("Cargo.toml").is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        // hidden directories are playgrounds which are still being created
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+
+    names.sort_unstable();
+
+    Ok(names)
+}
+
+/// Asks the user to pick one of the existing playgrounds which have all of `tags`. The
+/// playgrounds are shown with their descriptions.
+pub fn pick_playground(prompt: &str, tags: &[String]) -> error::Result<Option<String>> {
+    let mut playgrounds = Vec::new();
+    let mut options = Vec::new();
+
+    for name in list_playgrounds()? {
+        let metadata = Metadata::load(&get_dir().join(&name));
+
+        if metadata.has_tags(tags) {
+            options.push(match metadata.description {
+                Some(description) => format!("{} - {}", name, description),
+                None => name.clone(),
+            });
+            playgrounds.push(name);
+        }
+    }
+
+    if playgrounds.is_empty() {
+        let err = if tags.is_empty() {
+            error::Error::new(
+                io::ErrorKind::NotFound,
+                "there are no playgrounds to pick from",
+            )
+        } else {
+            error::Error::new(
+                io::ErrorKind::NotFound,
+                format!("there are no playgrounds tagged {}", tags.join(", ")),
+            )
+        };
+
+        return Err(err.with_help("use `cargo playground new` to create a new playground"));
+    }
+
+    let pick = pick_from(prompt, &options).map_err(|err| {
+        error::Error::new(
+            io::ErrorKind::Other,
+            format!("couldn't pick playground: {}", err),
+        )
+    })?;
+
+    Ok(pick.map(|i| playgrounds.swap_remove(i)))
+}
+
+/// Copies the contents of `from` into `to`, skipping the top level files and directories in `skip`.
+/// If a `transform` is given, the contents of text files are passed through it, and other files are
+/// copied as they are.
+pub fn copy_dir(
+    from: &Path,
+    to: &Path,
+    skip: &[&str],
+    transform: Option<&dyn Fn(&str) -> String>,
+) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in from.read_dir()? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+
+        if skip.iter().any(|skipped| file_name == *skipped) {
+            continue;
+        }
+
+        let dest = to.join(&file_name);
+
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &dest, &[], transform)?;
+            continue;
+        }
+
+        match transform {
+            Some(transform) => match String::from_utf8(fs::read(entry.path())?) {
+                Ok(contents) => fs::write(&dest, transform(&contents))?,
+                Err(err) => fs::write(&dest, err.into_bytes())?,
+            },
+            None => {
+                fs::copy(entry.path(), dest)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Recreates `from` at `to` with hard links to its files, skipping the top level files and
+/// directories in `skip`. Files which can't be linked (eg: because they are on another file system)
+/// are copied instead.
+pub fn link_dir(from: &Path, to: &Path, skip: &[&str]) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+
+    for entry in from.read_dir()? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+
+        if skip.iter().any(|skipped| file_name == *skipped) {
+            continue;
+        }
+
+        let dest = to.join(&file_name);
+        let file_type = entry.file_type()?;
+
+        if file_type.is_dir() {
+            link_dir(&entry.path(), &dest, &[])?;
+        } else if file_type.is_file() && fs::hard_link(entry.path(), &dest).is_err() {
+            fs::copy(entry.path(), dest)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Gets the total size of the files in `dir` and its subdirectories, in bytes. Files which can't be
+/// read are left out.
+pub fn dir_size(dir: &Path) -> u64 {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return 0,
+    };
+
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Formats a number of bytes with the largest unit it has one of, eg: `1.5G`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["K", "M", "G", "T"];
+
+    if bytes < 1024 {
+        return format!("{}B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1}{}", size, UNITS[unit])
+}
+
+/// Parses a size like `500M` or `1.5G`, in the units of `format_size`. Sizes without a unit are in
+/// bytes.
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' doesn't start with a number", size))?;
+
+    let multiplier = match unit.to_ascii_uppercase().trim_end_matches('B') {
+        "" => 1u64,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(format!("unknown unit '{}', use K, M, G or T", unit)),
+    };
+
+    let bytes = number * multiplier as f64;
+
+    // a float cast saturates, so sizes which don't fit would silently become u64::MAX
+    if !bytes.is_finite() || bytes >= u64::MAX as f64 {
+        return Err(format!("'{}' is too large", size));
+    }
+
+    Ok(bytes as u64)
+}
+
 /// Starts a loader on a new thread.
 pub fn loader(prompt: &'static str, stop: Arc<atomic::AtomicBool>) -> thread::JoinHandle<()> {
     thread::spawn(move || {
@@ -84,88 +315,91 @@ impl Drop for RawMode {
 }
 
 pub fn pick_from<T: AsRef<str>>(prompt: &str, from: &[T]) -> crossterm::Result<Option<usize>> {
+    if from.is_empty() {
+        return Ok(None);
+    }
+
     let _raw = RawMode::enable()?;
 
-    let th = terminal::size()?.1;
+    let (tw, th) = terminal::size()?;
+
+    // Items are cut to the width of the screen, as a wrapped item would throw off the rows below
+    let width = usize::from(tw.saturating_sub(2));
 
-    // FIXME: handle lists longer than screen
-    let from_len = u16::try_from(from.len()).unwrap();
-    assert!(from_len <= th);
+    // Lists longer than the screen are shown a page at a time, leaving a row for the prompt
+    let height = from.len().min(usize::from(th.saturating_sub(1)).max(1));
+    let rows = u16::try_from(height + 1).unwrap_or(u16::MAX);
 
     let ch = cursor::position()?.1;
 
     let mut stdout = io::stdout();
 
-    let mut base_row = ch + 1;
+    let mut top = ch;
 
-    if ch > th - from_len - 1 {
-        let dist = ch + from_len + 1 - th;
-        base_row -= dist;
+    if ch + rows > th {
+        let dist = (ch + rows - th).min(ch);
+        top -= dist;
         queue!(stdout, terminal::ScrollUp(dist), cursor::MoveUp(dist))?;
     }
 
     queue!(
         stdout,
+        cursor::MoveTo(0, top),
         Print("  "),
         PrintStyledContent(style(prompt).attribute(Attribute::Bold)),
         PrintStyledContent(style(" (use arrow keys or j/k)").with(Color::DarkGrey)),
-        cursor::MoveToNextLine(1),
     )?;
 
     let mut currently_at = 0;
+    // the index of the first item shown
+    let mut offset = 0;
 
-    for (i, from) in from.iter().enumerate() {
-        println(from.as_ref(), i == currently_at, &mut stdout)?;
-    }
+    let draw = |stdout: &mut io::Stdout, currently_at: usize, offset: usize| {
+        queue!(stdout, cursor::MoveTo(0, top + 1))?;
 
-    execute!(stdout, cursor::MoveTo(1, base_row))?;
+        for (i, from) in from.iter().enumerate().skip(offset).take(height) {
+            let item: String = from.as_ref().chars().take(width).collect();
+            println(&item, i == currently_at, stdout)?;
+        }
+
+        stdout.flush()
+    };
+
+    draw(&mut stdout, currently_at, offset)?;
+
+    let last = from.len() - 1;
 
     let res = loop {
         if let event::Event::Key(e) = event::read()? {
-            match e.code {
+            currently_at = match e.code {
                 event::KeyCode::Char('c') if e.modifiers.contains(event::KeyModifiers::CONTROL) => {
                     break None
                 }
                 event::KeyCode::Esc | event::KeyCode::Null => break None,
-                event::KeyCode::Up | event::KeyCode::Char('k') if currently_at != 0 => {
-                    print(from[currently_at].as_ref(), false, &mut stdout)?;
-                    currently_at -= 1;
-                    queue!(stdout, cursor::MoveToPreviousLine(1))?;
-                }
-                event::KeyCode::Down | event::KeyCode::Char('j')
-                    if currently_at < from.len() - 1 =>
-                {
-                    println(from[currently_at].as_ref(), false, &mut stdout)?;
-                    currently_at += 1;
-                }
-                event::KeyCode::Home | event::KeyCode::PageUp | event::KeyCode::Char('g')
-                    if currently_at != 0 =>
-                {
-                    print(from[currently_at].as_ref(), false, &mut stdout)?;
-                    queue!(stdout, cursor::MoveTo(0, base_row))?;
-                    currently_at = 0;
-                }
-                event::KeyCode::End | event::KeyCode::PageDown | event::KeyCode::Char('G')
-                    if currently_at != from.len() - 1 =>
-                {
-                    print(from[currently_at].as_ref(), false, &mut stdout)?;
-                    currently_at = from.len() - 1;
-                    queue!(stdout, cursor::MoveTo(0, base_row + from_len - 1))?;
-                }
-                event::KeyCode::Enter => {
-                    break Some(currently_at);
-                }
+                event::KeyCode::Enter => break Some(currently_at),
+                event::KeyCode::Up | event::KeyCode::Char('k') => currently_at.saturating_sub(1),
+                event::KeyCode::Down | event::KeyCode::Char('j') => (currently_at + 1).min(last),
+                event::KeyCode::PageUp => currently_at.saturating_sub(height),
+                event::KeyCode::PageDown => (currently_at + height).min(last),
+                event::KeyCode::Home | event::KeyCode::Char('g') => 0,
+                event::KeyCode::End | event::KeyCode::Char('G') => last,
                 _ => continue,
+            };
+
+            // scroll just enough to keep the selected item on the screen
+            if currently_at < offset {
+                offset = currently_at;
+            } else if currently_at >= offset + height {
+                offset = currently_at + 1 - height;
             }
 
-            print(from[currently_at].as_ref(), true, &mut stdout)?;
-            stdout.flush()?;
+            draw(&mut stdout, currently_at, offset)?;
         }
     };
 
     execute!(
         stdout,
-        cursor::MoveTo(0, base_row - 1),
+        cursor::MoveTo(0, top),
         terminal::Clear(terminal::ClearType::FromCursorDown)
     )?;
 
@@ -198,3 +432,25 @@ fn println(name: &str, is_selected: bool, stdout: &mut io::Stdout) -> crossterm:
     print(name, is_selected, stdout)?;
     queue!(stdout, cursor::MoveToNextLine(1))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("500MB"), Ok(500 << 20));
+        assert_eq!(parse_size("1.5G"), Ok(3 << 29));
+        assert_eq!(parse_size("2k"), Ok(2048));
+        assert_eq!(parse_size("100"), Ok(100));
+    }
+
+    #[test]
+    fn rejects_invalid_sizes() {
+        assert!(parse_size("5X").is_err());
+        assert!(parse_size("G").is_err());
+        // overflowing
+        assert!(parse_size("99999999T").is_err());
+        assert!(parse_size(&"9".repeat(400)).is_err());
+    }
+}
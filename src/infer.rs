@@ -0,0 +1,300 @@
+use crate::{dep::Dep, error, helpers, manifest::Manifest, registry};
+use crossterm::style::Stylize;
+use regex::Regex;
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Names which can be at the root of a path without being a crate.
+const NOT_CRATES: &[&str] = &[
+    "std",
+    "core",
+    "alloc",
+    "proc_macro",
+    "test",
+    "crate",
+    "self",
+    "super",
+    "bool",
+    "char",
+    "str",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "usize",
+    "f32",
+    "f64",
+];
+
+/// Looks for crates used by the code of the playground which aren't dependencies yet, and offers
+/// to add them. Returns the dependencies which should be added.
+pub fn offer(playground: &Path, manifest: &Manifest) -> error::Result<Vec<Dep>> {
+    let missing = missing_deps(playground, manifest)?;
+
+    if missing.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let prompt = format!(
+        "Add the crates used by the code as dependencies? ({})",
+        missing.join(", ")
+    );
+
+    let pick = match helpers::pick_from(&prompt, &["Yes", "No"]) {
+        Ok(pick) => pick,
+        // the crates are only a convenience, so not being able to ask about them (eg: without a
+        // terminal) isn't an error
+        Err(err) => {
+            eprintln!(
+                "{}: couldn't ask about adding the crates used by the code: {}",
+                "warning".dark_yellow().bold(),
+                err
+            );
+            eprintln!(
+                " {}: add them with `cargo playground add` if they are needed: {}",
+                "help".dark_yellow().bold(),
+                missing.join(", ")
+            );
+
+            return Ok(Vec::new());
+        }
+    };
+
+    match pick {
+        // Selected 'Yes'
+        Some(0) => missing.iter().map(|name| Dep::try_parse(name)).collect(),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Finds the crates used by the code of the playground which aren't dependencies yet. Only crates
+/// in the local registry cache are returned, as there is no other way to know whether a name is a
+/// crate without the network.
+fn missing_deps(playground: &Path, manifest: &Manifest) -> error::Result<Vec<String>> {
+    let mut files = Vec::new();
+    rust_files(playground, &mut files)?;
+
+    let mut roots = BTreeSet::new();
+    let mut locals = HashSet::new();
+
+    for file in files {
+        // the files of the playground are all expected to be text
+        if let Ok(source) = fs::read_to_string(file) {
+            path_roots(&source, &mut roots, &mut locals);
+        }
+    }
+
+    let deps: HashSet<_> = manifest
+        .dependency_names()
+        .into_iter()
+        .map(|name| name.replace('-', "_"))
+        .collect();
+
+    let cached = registry::cached_crates()?;
+
+    let missing = roots
+        .into_iter()
+        .filter(|root| {
+            !NOT_CRATES.contains(&root.as_str()) && !locals.contains(root) && !deps.contains(root)
+        })
+        // crates with a `-` in their name are used with a `_` instead
+        .filter_map(|root| {
+            cached
+                .keys()
+                .find(|name| **name == root)
+                .or_else(|| cached.keys().find(|name| name.replace('-', "_") == root))
+                .cloned()
+        })
+        .collect();
+
+    Ok(missing)
+}
+
+/// Collects the names at the root of the paths in `use` declarations, `extern crate`s and other
+/// paths in the code into `roots`. The names of local modules and of items brought into scope by
+/// `use` go into `locals`, as paths starting with them don't refer to crates.
+fn path_roots(source: &str, roots: &mut BTreeSet<String>, locals: &mut HashSet<String>) {
+    let comment = Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap();
+    let use_decl = Regex::new(r"\buse\s+([^;]*);").unwrap();
+    let extern_crate = Regex::new(r"\bextern\s+crate\s+(\w+)(?:\s+as\s+(\w+))?").unwrap();
+    let module = Regex::new(r"\bmod\s+(\w+)").unwrap();
+    // Crate names are lowercase, unlike most types which are also used at the root of paths. The
+    // path has to continue with a name, so that turbofishes like `parse::<T>` are skipped
+    let path = Regex::new(r"(?:^|[^\w:])([a-z_][a-z0-9_]*)\s*::\s*[A-Za-z_{*]").unwrap();
+
+    let source = comment.replace_all(source, "");
+
+    for captures in use_decl.captures_iter(&source) {
+        use_roots(&captures[1], roots, locals);
+    }
+
+    // The paths in `use` declarations are already handled, and their nested paths don't start
+    // with crates
+    let source = use_decl.replace_all(&source, "");
+
+    for captures in extern_crate.captures_iter(&source) {
+        roots.insert(captures[1].to_owned());
+        if let Some(alias) = captures.get(2) {
+            locals.insert(alias.as_str().to_owned());
+        }
+    }
+
+    for captures in module.captures_iter(&source) {
+        locals.insert(captures[1].to_owned());
+    }
+
+    for captures in path.captures_iter(&source) {
+        roots.insert(captures[1].to_owned());
+    }
+}
+
+/// Collects the roots and the imported names of the tree in a `use` declaration, such as
+/// `rand::{Rng, thread_rng}` or `{serde::Serialize, std::io}`.
+fn use_roots(tree: &str, roots: &mut BTreeSet<String>, locals: &mut HashSet<String>) {
+    let token = Regex::new(r"\w+|::|[{},*]").unwrap();
+    let tokens: Vec<_> = token.find_iter(tree).map(|t| t.as_str()).collect();
+
+    // whether the tree starts with a group, like `{a::b, c}` or `::{a::b, c}`
+    let grouped = tokens.iter().find(|t| **t != "::") == Some(&"{");
+    // the paths the enclosing groups are in, eg: `std::io` for `std::io::{self, Write}`
+    let mut groups = Vec::new();
+
+    for (i, &token) in tokens.iter().enumerate() {
+        let prev = tokens[..i].iter().rev().find(|t| **t != "::").copied();
+        let next = tokens.get(i + 1).copied();
+
+        match token {
+            "{" => groups.push(prev),
+            "}" => {
+                groups.pop();
+            }
+            // `self` in a group imports the module the group is in
+            "self" => {
+                if let Some(Some(module)) = groups.last() {
+                    locals.insert(module.to_string());
+                }
+            }
+            "," | "::" | "*" | "as" => {}
+            _ => {
+                let is_root = match prev {
+                    None => true,
+                    Some("{") | Some(",") => grouped && groups.len() == 1,
+                    _ => false,
+                };
+
+                if is_root {
+                    roots.insert(token.to_owned());
+                } else if next != Some("::") && next != Some("as") {
+                    // the last segment of a path, or the alias given to it with `as`
+                    locals.insert(token.to_owned());
+                }
+            }
+        }
+    }
+}
+
+/// Lists the rust files in the playground, skipping its target directory and hidden directories.
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in dir.read_dir()?.flatten() {
+        let path = entry.path();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+        if path.is_dir() {
+            if !hidden && entry.file_name() != "target" {
+                rust_files(&path, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roots(source: &str) -> (Vec<String>, Vec<String>) {
+        let mut roots = BTreeSet::new();
+        let mut locals = HashSet::new();
+        path_roots(source, &mut roots, &mut locals);
+
+        let mut locals: Vec<_> = locals.into_iter().collect();
+        locals.sort();
+
+        (roots.into_iter().collect(), locals)
+    }
+
+    #[test]
+    fn finds_nested_use_trees() {
+        assert_eq!(
+            roots("use std::{io::{self, Write}, fs};"),
+            (
+                vec!["std".into()],
+                vec!["Write".into(), "fs".into(), "io".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn finds_roots_in_leading_groups() {
+        assert_eq!(
+            roots("use {serde::Serialize, std::io};"),
+            (
+                vec!["serde".into(), "std".into()],
+                vec!["Serialize".into(), "io".into()]
+            )
+        );
+        assert_eq!(roots("use ::{rand::Rng};").0, ["rand"]);
+    }
+
+    #[test]
+    fn records_aliases() {
+        assert_eq!(
+            roots("use foo as bar;"),
+            (vec!["foo".into()], vec!["bar".into()])
+        );
+        assert_eq!(
+            roots("extern crate x as y;"),
+            (vec!["x".into()], vec!["y".into()])
+        );
+    }
+
+    #[test]
+    fn finds_paths_in_code() {
+        let (roots, locals) = roots(
+            "mod util;\n\
+             fn main() {\n\
+                 let n: u8 = rand::random();\n\
+                 util::helper(regex :: Regex::new(\"a\"));\n\
+             }",
+        );
+
+        assert_eq!(roots, ["rand", "regex", "util"]);
+        assert_eq!(locals, ["util"]);
+    }
+
+    #[test]
+    fn skips_turbofishes_and_comments() {
+        let (roots, _) = roots(
+            "// serde::Serialize\n\
+             /* tokio::main */\n\
+             fn main() {\n\
+                 let n = \"1\".parse::<u32>().unwrap();\n\
+                 let v = (0..n).collect::<Vec<_>>();\n\
+                 let x = itertools::repeat_n::<u8>(0, 1);\n\
+             }",
+        );
+
+        assert_eq!(roots, ["itertools"]);
+    }
+}
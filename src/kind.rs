@@ -0,0 +1,160 @@
+use crate::{error, manifest::Manifest};
+use std::path::Path;
+use std::{fs, io, str::FromStr};
+
+const LIB_RS: &str = r#"pub fn add(left: usize, right: usize) -> usize {
+    left + right
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_works() {
+        assert_eq!(add(2, 2), 4);
+    }
+}
+"#;
+
+const TEST_RS: &str = r#"#[test]
+fn it_works() {
+    assert_eq!(2 + 2, 4);
+}
+"#;
+
+const BENCH_RS: &str = r#"use std::hint::black_box;
+use std::time::Instant;
+
+const ITERATIONS: u32 = 1_000_000;
+
+fn main() {
+    let start = Instant::now();
+
+    for i in 0..ITERATIONS {
+        black_box(i);
+    }
+
+    println!("{:?} per iteration", start.elapsed() / ITERATIONS);
+}
+"#;
+
+/// The name of the bench target created for bench playgrounds
+const BENCH_NAME: &str = "playground";
+
+/// The kind of code a playground is for, which decides its entry file and how it is run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Bin,
+    Lib,
+    Test,
+    Bench,
+}
+
+impl Kind {
+    pub const VARIANTS: &'static [&'static str] = &["bin", "lib", "test", "bench"];
+
+    /// Reads the kind of the playground from its manifest. Playgrounds which were created before
+    /// kinds existed are binaries.
+    pub fn of(manifest: &Manifest) -> error::Result<Self> {
+        match manifest.playground_metadata("kind") {
+            Some(kind) => kind.parse(),
+            None => Ok(Kind::Bin),
+        }
+    }
+
+    /// The path to the file which is opened in the editor, relative to the playground.
+    pub fn entry(self) -> &'static str {
+        match self {
+            Kind::Bin => "src/main.rs",
+            Kind::Lib | Kind::Test => "src/lib.rs",
+            Kind::Bench => "benches/playground.rs",
+        }
+    }
+
+    /// The command the watcher runs whenever the playground changes.
+    pub fn command(self) -> &'static str {
+        match self {
+            Kind::Bin => "cargo run -q",
+            Kind::Lib | Kind::Test => "cargo test -q",
+            Kind::Bench => "cargo bench -q",
+        }
+    }
+
+    /// The flag to pass to `cargo new` to create the playground.
+    pub fn cargo_new_flag(self) -> &'static str {
+        match self {
+            Kind::Bin => "--bin",
+            Kind::Lib | Kind::Test | Kind::Bench => "--lib",
+        }
+    }
+
+    /// Sets up a playground freshly created by `cargo new`, replacing the default files with ones
+    /// suited to the kind and recording it in the manifest.
+    pub fn scaffold(self, playground: &Path, manifest: &mut Manifest) -> error::Result<()> {
+        let contents = match self {
+            Kind::Bin => return Ok(()),
+            Kind::Lib => LIB_RS,
+            Kind::Test => TEST_RS,
+            Kind::Bench => BENCH_RS,
+        };
+
+        let entry = playground.join(self.entry());
+        if let Some(parent) = entry.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&entry, contents)?;
+
+        if self == Kind::Bench {
+            manifest.add_target("bench", BENCH_NAME, false)?;
+        }
+
+        self.record(manifest)
+    }
+
+    /// Guesses the kind of an existing cargo project which wasn't created as a playground. Projects
+    /// with only a library are library playgrounds, everything else is a binary.
+    pub fn detect(project: &Path) -> Self {
+        if !project.join(Kind::Bin.entry()).is_file() && project.join(Kind::Lib.entry()).is_file() {
+            Kind::Lib
+        } else {
+            Kind::Bin
+        }
+    }
+
+    /// Records the kind in the manifest. Binaries are the default, so they aren't recorded.
+    pub fn record(self, manifest: &mut Manifest) -> error::Result<()> {
+        if self == Kind::Bin {
+            return Ok(());
+        }
+
+        manifest.set_playground_metadata("kind", self.as_str())
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Kind::Bin => "bin",
+            Kind::Lib => "lib",
+            Kind::Test => "test",
+            Kind::Bench => "bench",
+        }
+    }
+}
+
+impl FromStr for Kind {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> error::Result<Self> {
+        match s {
+            "bin" => Ok(Kind::Bin),
+            "lib" => Ok(Kind::Lib),
+            "test" => Ok(Kind::Test),
+            "bench" => Ok(Kind::Bench),
+            _ => Err(error::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown playground kind '{}'", s),
+            )
+            .with_help("the kind must be one of bin, lib, test or bench")),
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::error;
+use std::collections::HashMap;
+use std::path::Path;
+use std::{fs, io};
+use toml_edit::Document;
+
+/// Reads the versions that the direct dependencies of `package` were resolved to in the Cargo.lock
+/// at `path`. The returned map is from the package name of a dependency to its version.
+pub fn resolved_versions(path: &Path, package: &str) -> error::Result<HashMap<String, String>> {
+    let invalid = || {
+        error::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{:?} is not a valid Cargo.lock", path),
+        )
+    };
+
+    let contents = fs::read_to_string(path).map_err(|err| {
+        error::Error::new(err.kind(), format!("couldn't read {:?}: {}", path, err))
+    })?;
+    let doc = contents.parse::<Document>().map_err(|_| invalid())?;
+
+    let packages = doc
+        .get("package")
+        .and_then(|p| p.as_array_of_tables())
+        .ok_or_else(invalid)?;
+
+    // (name, version) for every package in the lock file
+    let locked: Vec<_> = packages
+        .iter()
+        .filter_map(|p| Some((p.get("name")?.as_str()?, p.get("version")?.as_str()?)))
+        .collect();
+
+    let root = packages
+        .iter()
+        .find(|p| {
+            p.get("source").is_none() && p.get("name").and_then(|n| n.as_str()) == Some(package)
+        })
+        .ok_or_else(invalid)?;
+
+    let mut versions = HashMap::new();
+
+    let deps = match root.get("dependencies").and_then(|d| d.as_array()) {
+        Some(deps) => deps,
+        None => return Ok(versions),
+    };
+
+    // Each dependency is either "<name>", or "<name> <version>" if there are multiple versions of
+    // it in the lock file, optionally followed by the source
+    for dep in deps.iter().filter_map(|d| d.as_str()) {
+        let mut parts = dep.split(' ');
+        let name = parts.next().unwrap_or_default();
+
+        let version = match parts.next() {
+            Some(version) => Some(version),
+            None => locked
+                .iter()
+                .find(|(locked_name, _)| *locked_name == name)
+                .map(|(_, version)| *version),
+        };
+
+        if let Some(version) = version {
+            versions.insert(name.to_owned(), version.to_owned());
+        }
+    }
+
+    Ok(versions)
+}
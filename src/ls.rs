@@ -0,0 +1,306 @@
+use crate::{error, helpers, manifest::Manifest, metadata, metadata::Metadata, toolchain};
+use crossterm::style::Stylize;
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use std::{fs, io, str::FromStr};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct LsOpts {
+    /// What to sort the playgrounds by. `created` and `modified` put the newest first, and `size`
+    /// (of the target directory) puts the largest first. A playground is modified when its files
+    /// are changed or it is opened
+    #[structopt(long, default_value = "name", possible_values = Sort::VARIANTS)]
+    sort: Sort,
+    /// Show when the playgrounds were created and modified, their dependencies, the number of
+    /// lines of rust code and the size of their target directory
+    #[structopt(short, long)]
+    long: bool,
+    /// Print the details of the playgrounds as JSON, with times in seconds since the unix epoch
+    #[structopt(long, conflicts_with = "long")]
+    json: bool,
+    /// Only list the playgrounds with this tag. Can be given multiple times
+    #[structopt(long = "tag", number_of_values = 1)]
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sort {
+    Name,
+    Created,
+    Modified,
+    Size,
+}
+
+impl Sort {
+    const VARIANTS: &'static [&'static str] = &["name", "created", "modified", "size"];
+}
+
+impl FromStr for Sort {
+    type Err = error::Error;
+
+    fn from_str(s: &str) -> error::Result<Self> {
+        match s {
+            "name" => Ok(Sort::Name),
+            "created" => Ok(Sort::Created),
+            "modified" => Ok(Sort::Modified),
+            "size" => Ok(Sort::Size),
+            _ => Err(error::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown sort order '{}'", s),
+            )
+            .with_help("the sort order must be one of name, created, modified or size")),
+        }
+    }
+}
+
+/// The details of a playground which are listed.
+pub struct Details {
+    pub name: String,
+    pub metadata: Metadata,
+    edition: Option<String>,
+    toolchain: Option<String>,
+    deps: Vec<String>,
+    /// When a file of the playground (outside its target directory) was last changed
+    modified: Option<u64>,
+    /// The number of lines in the rust files of the playground
+    lines: usize,
+    /// The size of the target directory, which is only measured when it is needed as it can take
+    /// a while
+    target_size: Option<u64>,
+}
+
+impl Details {
+    pub fn collect(name: String, measure_target: bool) -> Self {
+        let path = helpers::get_dir().join(&name);
+        let manifest = Manifest::open(path.join("Cargo.toml")).ok();
+
+        let mut modified = None;
+        let mut lines = 0;
+        walk_sources(&path, &mut modified, &mut lines);
+
+        Details {
+            metadata: Metadata::load(&path),
+            edition: manifest
+                .as_ref()
+                .and_then(|manifest| manifest.package_field("edition").map(String::from)),
+            toolchain: toolchain::read(&path),
+            deps: manifest
+                .map(|manifest| manifest.dependency_names())
+                .unwrap_or_default(),
+            modified,
+            lines,
+            target_size: Some(&path)
+                .filter(|_| measure_target)
+                .map(|path| helpers::dir_size(&path.join("target"))),
+            name,
+        }
+    }
+
+    /// When the playground was last used, which is either when it was modified or opened.
+    pub fn last_used(&self) -> Option<u64> {
+        self.modified.max(self.metadata.last_opened)
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "name": self.name,
+            "path": helpers::get_dir().join(&self.name),
+            "created": self.metadata.created,
+            "modified": self.modified,
+            "last_opened": self.metadata.last_opened,
+            "last_run": self.metadata.last_run,
+            "edition": self.edition,
+            "toolchain": self.toolchain,
+            "description": self.metadata.description,
+            "tags": self.metadata.tags,
+            "pinned": self.metadata.pinned,
+            "dependencies": self.deps,
+            "lines": self.lines,
+            "target_size": self.target_size,
+        })
+    }
+}
+
+/// Lists the playgrounds, and optionally their details.
+pub fn ls(opts: LsOpts) -> error::Result<()> {
+    let measure_target = opts.long || opts.json || opts.sort == Sort::Size;
+
+    let mut playgrounds: Vec<_> = helpers::list_playgrounds()?
+        .into_iter()
+        .map(|name| Details::collect(name, measure_target))
+        .filter(|details| details.metadata.has_tags(&opts.tags))
+        .collect();
+
+    // the playgrounds are already sorted by name, and the sort is stable
+    match opts.sort {
+        Sort::Name => {}
+        Sort::Created => playgrounds.sort_by_key(|details| Reverse(details.metadata.created)),
+        Sort::Modified => playgrounds.sort_by_key(|details| Reverse(details.last_used())),
+        Sort::Size => playgrounds.sort_by_key(|details| Reverse(details.target_size)),
+    }
+
+    if opts.json {
+        let json: Vec<_> = playgrounds.iter().map(Details::to_json).collect();
+        println!("{}", Value::Array(json));
+    } else if opts.long {
+        print_long(&playgrounds);
+    } else {
+        for details in &playgrounds {
+            let mut line = details.name.clone();
+
+            match (&details.edition, &details.toolchain) {
+                (Some(edition), Some(toolchain)) => {
+                    let info = format!("(edition {}, {})", edition, toolchain);
+                    line = format!("{} {}", line, info.dark_grey());
+                }
+                (Some(edition), None) => {
+                    let info = format!("(edition {})", edition);
+                    line = format!("{} {}", line, info.dark_grey());
+                }
+                (None, Some(toolchain)) => {
+                    let info = format!("({})", toolchain);
+                    line = format!("{} {}", line, info.dark_grey());
+                }
+                _ => {}
+            }
+
+            if details.metadata.pinned {
+                line = format!("{} {}", line, "pinned".yellow());
+            }
+
+            if !details.metadata.tags.is_empty() {
+                line = format!("{} {}", line, tag_list(&details.metadata.tags).cyan());
+            }
+
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
+
+fn print_long(playgrounds: &[Details]) {
+    let name_width = playgrounds
+        .iter()
+        .map(|details| details.name.len())
+        .chain(Some("NAME".len()))
+        .max()
+        .unwrap_or_default();
+
+    println!(
+        "{}",
+        format!(
+            "{:name_width$}  {:>9}  {:>9}  {:>6}  {:>7}  DEPENDENCIES",
+            "NAME",
+            "CREATED",
+            "MODIFIED",
+            "LINES",
+            "TARGET",
+            name_width = name_width
+        )
+        .bold()
+    );
+
+    let now = metadata::now();
+    let age = |time: Option<u64>| time.map_or_else(|| "-".into(), |time| format_age(now, time));
+
+    for details in playgrounds {
+        println!(
+            "{:name_width$}  {:>9}  {:>9}  {:>6}  {:>7}  {}",
+            details.name,
+            age(details.metadata.created),
+            age(details.last_used()),
+            details.lines,
+            details
+                .target_size
+                .map_or_else(|| "-".into(), helpers::format_size),
+            details.deps.join(", "),
+            name_width = name_width
+        );
+
+        // the description, tags and whether it is pinned don't fit in a column, so they go on a
+        // line of their own
+        let mut about = Vec::new();
+        if let Some(ref description) = details.metadata.description {
+            about.push(description.as_str().dark_grey());
+        }
+        let tags = tag_list(&details.metadata.tags);
+        if !tags.is_empty() {
+            about.push(tags.as_str().cyan());
+        }
+        if details.metadata.pinned {
+            about.push("pinned".yellow());
+        }
+
+        if !about.is_empty() {
+            let about: Vec<_> = about.iter().map(ToString::to_string).collect();
+            println!("  {}", about.join(" "));
+        }
+    }
+}
+
+/// Formats tags like `#foo #bar`.
+fn tag_list(tags: &[String]) -> String {
+    tags.iter()
+        .map(|tag| format!("#{}", tag))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats how long ago `time` was in its largest unit, eg: `3d ago`.
+fn format_age(now: u64, time: u64) -> String {
+    const UNITS: &[(u64, &str)] = &[
+        (60 * 60 * 24 * 365, "y"),
+        (60 * 60 * 24 * 7, "w"),
+        (60 * 60 * 24, "d"),
+        (60 * 60, "h"),
+        (60, "m"),
+    ];
+
+    let secs = now.saturating_sub(time);
+
+    UNITS.iter().find(|(unit, _)| secs >= *unit).map_or_else(
+        || "just now".into(),
+        |(unit, name)| format!("{}{} ago", secs / unit, name),
+    )
+}
+
+/// Finds when the files of a playground were last modified, and counts the lines of its rust
+/// files. The target directory and hidden files, such as the metadata, are skipped.
+fn walk_sources(dir: &Path, modified: &mut Option<u64>, lines: &mut usize) {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        if file_name == "target" || file_name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+
+        if path.is_dir() {
+            walk_sources(&path, modified, lines);
+            continue;
+        }
+
+        let time = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|time| time.as_secs());
+        *modified = (*modified).max(time);
+
+        if path.extension().is_some_and(|ext| ext == "rs") {
+            if let Ok(source) = fs::read_to_string(&path) {
+                *lines += source.lines().count();
+            }
+        }
+    }
+}
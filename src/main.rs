@@ -1,21 +1,45 @@
 use structopt::StructOpt;
 
 mod clean;
+mod clone;
+mod dep;
+mod deps;
 mod error;
+mod export;
 mod helpers;
+mod infer;
+mod kind;
+mod lockfile;
+mod ls;
+mod manifest;
+mod metadata;
 mod new;
 mod open;
+mod pin;
+mod registry;
+mod rename;
+mod run;
+mod script;
+mod share;
+mod tags;
+mod templates;
+mod toolchain;
 mod watch;
 
 #[derive(StructOpt, Debug)]
 /// Make and use playgrounds locally.
 #[structopt(bin_name = "cargo", usage = "cargo playground <SUBCOMMAND>")]
+// The options are only parsed once, so their size doesn't matter
+#[allow(clippy::large_enum_variant)]
 enum Opts {
     // FIXME: See if this can be hidden from help message
     /// Internal command required for running the playground -- good idea not to use it
     Watch {
         playground_id: String,
     },
+    /// Internal command used by `watch` to run the playground and record its output
+    #[structopt(setting = structopt::clap::AppSettings::Hidden)]
+    Run,
     Playground(PlaygroundOpts),
 }
 
@@ -28,13 +52,40 @@ enum PlaygroundOpts {
     New(new::NewOpts),
     /// Opens an already existing playground
     // Override the default because it include '--editor <editor>'
-    #[structopt(usage = "cargo playground open [FLAGS] [OPTIONS] <name>")]
+    #[structopt(usage = "cargo playground open [FLAGS] [OPTIONS] [name]")]
     Open(open::OpenOpts),
+    /// Copies an existing playground to a new one and opens it
+    // Override the default because it include '--editor <editor>'
+    #[structopt(usage = "cargo playground clone [FLAGS] [OPTIONS] <src> [dst]")]
+    Clone(clone::CloneOpts),
+    /// Renames a playground
+    Mv(rename::MvOpts),
+    /// Adds dependencies to an existing playground
+    Add(deps::AddOpts),
+    /// Removes dependencies from an existing playground
+    #[structopt(alias = "rm")]
+    Remove(deps::RemoveOpts),
+    /// Adds and removes the tags of a playground
+    Tag(tags::TagOpts),
+    /// Sets or prints the description of a playground
+    Describe(tags::DescribeOpts),
+    /// Pins a playground, so that it is kept by `clean`
+    Pin(pin::PinOpts),
+    /// Unpins a playground, so that it can be deleted by `clean` again
+    Unpin(pin::PinOpts),
+    /// Saves an existing playground as a named template, which can be used with `new -T`
+    SaveTemplate(templates::SaveTemplateOpts),
+    /// Exports a playground as a single file cargo script
+    Export(export::ExportOpts),
+    /// Creates a playground from a single file cargo script, such as one made by `export`
+    Import(export::ImportOpts),
+    /// Prints a playground's code, dependencies, toolchain and last output for sharing
+    Share(share::ShareOpts),
     /// Cleans the playgrounds directory, deleting all cargo projects in it.
     Clean(clean::CleanOpts),
     /// List currently existing playgrounds
     #[structopt(alias = "list")]
-    Ls,
+    Ls(ls::LsOpts),
 }
 
 #[derive(StructOpt, Debug)]
@@ -59,30 +110,27 @@ fn run() -> error::Result<()> {
     let opts = match opts {
         Opts::Playground(opts) => opts,
         Opts::Watch { playground_id } => {
-            watch::watch(&playground_id);
-
-            return Ok(());
+            return watch::watch(&playground_id);
         }
+        Opts::Run => return run::run(),
     };
 
     match opts {
         PlaygroundOpts::New(opts) => new::new(opts),
         PlaygroundOpts::Open(opts) => open::open(opts),
+        PlaygroundOpts::Clone(opts) => clone::clone(opts),
+        PlaygroundOpts::Mv(opts) => rename::mv(opts),
+        PlaygroundOpts::Add(opts) => deps::add(opts),
+        PlaygroundOpts::Remove(opts) => deps::remove(opts),
+        PlaygroundOpts::Tag(opts) => tags::tag(opts),
+        PlaygroundOpts::Describe(opts) => tags::describe(opts),
+        PlaygroundOpts::Pin(opts) => pin::pin(opts, true),
+        PlaygroundOpts::Unpin(opts) => pin::pin(opts, false),
+        PlaygroundOpts::SaveTemplate(opts) => templates::save(opts),
+        PlaygroundOpts::Export(opts) => export::export(opts),
+        PlaygroundOpts::Import(opts) => export::import(opts),
+        PlaygroundOpts::Share(opts) => share::share(opts),
         PlaygroundOpts::Clean(opts) => clean::clean(opts),
-        PlaygroundOpts::Ls => {
-            let path = helpers::get_dir();
-            if !path.exists() {
-                return Ok(());
-            }
-
-            // ignoring errors for now, maybe do something about it?
-            for entry in path.read_dir()?.flatten() {
-                if let Some(name) = entry.file_name().to_str() {
-                    println!("{}", name);
-                }
-            }
-
-            Ok(())
-        }
+        PlaygroundOpts::Ls(opts) => ls::ls(opts),
     }
 }
@@ -0,0 +1,592 @@
+use crate::{dep::Dep, error};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+use toml_edit::{value, Document, Item, TableLike, Value};
+
+/// The tables which configure the targets of a package.
+const TARGETS: &[&str] = &["lib", "bin", "example", "test", "bench"];
+
+/// A playground's Cargo.toml, which can be edited without losing its formatting.
+pub struct Manifest {
+    path: PathBuf,
+    doc: Document,
+}
+
+impl Manifest {
+    /// Reads and parses the manifest at `path`.
+    pub fn open<P: Into<PathBuf>>(path: P) -> error::Result<Self> {
+        let path = path.into();
+        let contents = fs::read_to_string(&path).map_err(|err| {
+            error::Error::new(err.kind(), format!("couldn't read {:?}: {}", path, err))
+        })?;
+
+        let doc = contents.parse::<Document>().map_err(|err| {
+            error::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("couldn't parse {:?}: {}", path, err),
+            )
+        })?;
+
+        Ok(Self { path, doc })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the manifest back to where it was read from.
+    pub fn save(&self) -> error::Result<()> {
+        fs::write(&self.path, self.doc.to_string()).map_err(|err| {
+            error::Error::new(
+                err.kind(),
+                format!("couldn't write {:?}: {}", self.path, err),
+            )
+        })
+    }
+
+    /// Adds a dependency. If the dependency is already present, the two are merged, and an error
+    /// is returned if they conflict.
+    pub fn add_dep(&mut self, dep: &Dep) -> error::Result<()> {
+        merge_dep(self.dependencies_mut()?, dep)
+    }
+
+    /// Merges a manifest fragment (eg: from a template) into this manifest.
+    ///
+    /// Dependencies are merged like [`Manifest::add_dep`], features and other arrays are combined,
+    /// and the values in `[package]` (except the name) are overwritten. Any other value which is
+    /// already present with a different value is a conflict.
+    pub fn merge(&mut self, fragment: &str) -> error::Result<()> {
+        let fragment = fragment.parse::<Document>().map_err(|err| {
+            error::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("couldn't parse manifest fragment: {}", err),
+            )
+        })?;
+
+        for (key, item) in fragment.iter() {
+            let existing = self.doc.as_table_mut().entry(key).or_insert(Item::None);
+
+            match key {
+                "package" => {
+                    let mut item = item.clone();
+                    if let Some(package) = item.as_table_like_mut() {
+                        package.remove("name");
+                    }
+                    merge_item(key, existing, &item, true)?;
+                }
+                "dependencies" | "dev-dependencies" | "build-dependencies" => {
+                    if existing.is_none() {
+                        *existing = toml_edit::table();
+                    }
+
+                    let (existing, deps) =
+                        match (existing.as_table_like_mut(), item.as_table_like()) {
+                            (Some(existing), Some(deps)) => (existing, deps),
+                            _ => return Err(conflict(key)),
+                        };
+
+                    for (name, item) in deps.iter() {
+                        match Dep::from_item(name, item) {
+                            Some(dep) => merge_dep(existing, &dep)?,
+                            None => {
+                                let path = format!("{}.{}", key, name);
+                                merge_item(
+                                    &path,
+                                    existing.entry(name).or_insert(Item::None),
+                                    item,
+                                    false,
+                                )?
+                            }
+                        }
+                    }
+                }
+                _ => merge_item(key, existing, item, false)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes a dependency, returning whether it was present.
+    pub fn remove_dep(&mut self, name: &str) -> error::Result<bool> {
+        Ok(self.dependencies_mut()?.remove(name).is_some())
+    }
+
+    /// Lists the dependencies in the `[dependencies]` table.
+    pub fn dependencies(&self) -> Vec<Dep> {
+        self.doc
+            .get("dependencies")
+            .and_then(Item::as_table_like)
+            .into_iter()
+            .flat_map(|deps| deps.iter())
+            .filter_map(|(name, item)| Dep::from_item(name, item))
+            .collect()
+    }
+
+    /// Lists the names of the dependencies, including dev and build dependencies.
+    pub fn dependency_names(&self) -> Vec<String> {
+        ["dependencies", "dev-dependencies", "build-dependencies"]
+            .iter()
+            .filter_map(|table| self.doc.get(table)?.as_table_like())
+            .flat_map(|deps| deps.iter().map(|(name, _)| name.to_owned()))
+            .collect()
+    }
+
+    /// Replaces wildcard (`*`) versions of registry dependencies with the versions they were
+    /// resolved to. `versions` maps from package names to resolved versions.
+    pub fn pin_wildcards(&mut self, versions: &HashMap<String, String>) -> error::Result<()> {
+        let deps = self.dependencies_mut()?;
+
+        let pinned: Vec<_> = deps
+            .iter()
+            .filter_map(|(name, item)| Dep::from_item(name, item))
+            .filter(|dep| {
+                dep.version.as_deref() == Some("*") && dep.git.is_none() && dep.path.is_none()
+            })
+            .filter_map(|mut dep| {
+                dep.version = Some(versions.get(dep.package_name())?.clone());
+                Some(dep)
+            })
+            .collect();
+
+        for dep in pinned {
+            deps.insert(&dep.name, Item::Value(dep.to_value()));
+        }
+
+        Ok(())
+    }
+
+    /// Makes the relative paths of path dependencies and patches absolute, so that they still point
+    /// to the same crates once the manifest is moved away from `base`, the directory it was in.
+    pub fn absolutize_dep_paths(&mut self, base: &Path) {
+        for (key, item) in self.doc.iter_mut() {
+            match key.get() {
+                "dependencies" | "dev-dependencies" | "build-dependencies" => {
+                    absolutize_paths(item, base)
+                }
+                // `[target.<cfg>.dependencies]`
+                "target" => {
+                    let targets = item.as_table_like_mut().into_iter();
+                    for (_, target) in targets.flat_map(|t| t.iter_mut()) {
+                        let tables = target.as_table_like_mut().into_iter();
+                        for (key, deps) in tables.flat_map(|t| t.iter_mut()) {
+                            if key.get().ends_with("dependencies") {
+                                absolutize_paths(deps, base);
+                            }
+                        }
+                    }
+                }
+                // `[patch.<registry>]`
+                "patch" => {
+                    let registries = item.as_table_like_mut().into_iter();
+                    for (_, patches) in registries.flat_map(|t| t.iter_mut()) {
+                        absolutize_paths(patches, base);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Converts the manifest into one which can be embedded in a cargo script. A script is a single
+    /// binary named after its file, so the package name and targets are left out.
+    pub fn to_script_manifest(&self) -> String {
+        let mut doc = self.doc.clone();
+
+        if let Some(package) = doc.get_mut("package").and_then(Item::as_table_like_mut) {
+            package.remove("name");
+        }
+
+        for target in TARGETS {
+            doc.remove(target);
+        }
+
+        doc.to_string()
+    }
+
+    /// Gets a string value from the `[package]` table.
+    pub fn package_field(&self, key: &str) -> Option<&str> {
+        self.doc.get("package")?.get(key)?.as_str()
+    }
+
+    /// Sets a string value in the `[package]` table.
+    pub fn set_package_field(&mut self, key: &str, val: &str) -> error::Result<()> {
+        self.package_mut()?[key] = value(val);
+        Ok(())
+    }
+
+    /// Removes a value from the `[package]` table.
+    pub fn remove_package_field(&mut self, key: &str) -> error::Result<()> {
+        self.package_mut()?.remove(key);
+        Ok(())
+    }
+
+    /// Gets a value from the `[package.metadata.playground]` table.
+    pub fn playground_metadata(&self, key: &str) -> Option<&str> {
+        self.doc
+            .get("package")?
+            .get("metadata")?
+            .get("playground")?
+            .get(key)?
+            .as_str()
+    }
+
+    /// Sets a value in the `[package.metadata.playground]` table, creating it if required.
+    pub fn set_playground_metadata(&mut self, key: &str, val: &str) -> error::Result<()> {
+        let metadata = self
+            .package_mut()?
+            .entry("metadata")
+            .or_insert_with(implicit_table)
+            .as_table_mut();
+        let playground = metadata.and_then(|metadata| {
+            metadata
+                .entry("playground")
+                .or_insert_with(toml_edit::table)
+                .as_table_mut()
+        });
+
+        match playground {
+            Some(playground) => {
+                playground[key] = value(val);
+                Ok(())
+            }
+            None => Err(error::Error::new(
+                io::ErrorKind::InvalidData,
+                "`package.metadata.playground` in Cargo.toml is not a table",
+            )),
+        }
+    }
+
+    /// Adds a target (eg: `[[bench]]`) to the manifest.
+    pub fn add_target(&mut self, kind: &str, name: &str, harness: bool) -> error::Result<()> {
+        let targets = self
+            .doc
+            .as_table_mut()
+            .entry(kind)
+            .or_insert_with(|| Item::ArrayOfTables(Default::default()))
+            .as_array_of_tables_mut()
+            .ok_or_else(|| {
+                error::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("`{}` in Cargo.toml is not an array of tables", kind),
+                )
+            })?;
+
+        let mut target = toml_edit::Table::new();
+        target["name"] = value(name);
+        if !harness {
+            target["harness"] = value(false);
+        }
+        targets.push(target);
+
+        Ok(())
+    }
+
+    fn package_mut(&mut self) -> error::Result<&mut toml_edit::Table> {
+        self.doc["package"].as_table_mut().ok_or_else(|| {
+            error::Error::new(
+                io::ErrorKind::InvalidData,
+                "`package` in Cargo.toml is not a table",
+            )
+        })
+    }
+
+    fn dependencies_mut(&mut self) -> error::Result<&mut dyn TableLike> {
+        self.doc
+            .as_table_mut()
+            .entry("dependencies")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or_else(|| {
+                error::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "`dependencies` in Cargo.toml is not a table",
+                )
+            })
+    }
+}
+
+/// Makes the relative `path`s of the dependencies in a dependency table absolute.
+fn absolutize_paths(deps: &mut Item, base: &Path) {
+    let deps = match deps.as_table_like_mut() {
+        Some(deps) => deps,
+        None => return,
+    };
+
+    for (_, dep) in deps.iter_mut() {
+        let path = match dep.get_mut("path").and_then(Item::as_value_mut) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let absolute = match path.as_str() {
+            Some(relative) if Path::new(relative).is_relative() => {
+                let absolute = base.join(relative);
+                absolute.canonicalize().unwrap_or(absolute)
+            }
+            _ => continue,
+        };
+
+        let decor = path.decor().clone();
+        *path = absolute.to_string_lossy().as_ref().into();
+        *path.decor_mut() = decor;
+    }
+}
+
+fn implicit_table() -> Item {
+    let mut table = toml_edit::Table::new();
+    table.set_implicit(true);
+    Item::Table(table)
+}
+
+/// Adds a dependency to a dependency table, merging it with the existing entry if present.
+fn merge_dep(deps: &mut dyn TableLike, dep: &Dep) -> error::Result<()> {
+    let merged = match deps.get(&dep.name) {
+        Some(existing) => {
+            let mut existing = Dep::from_item(&dep.name, existing).ok_or_else(|| {
+                error::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "dependency '{}' is already present in Cargo.toml, and it cannot be merged",
+                        dep.name
+                    ),
+                )
+                .with_help("edit the Cargo.toml of the playground directly")
+            })?;
+
+            existing.merge(dep)?;
+            existing
+        }
+        None => dep.clone(),
+    };
+
+    deps.insert(&merged.name, Item::Value(merged.to_value()));
+
+    Ok(())
+}
+
+/// Merges `new` into `existing`. Tables are merged key by key, arrays are combined, and arrays of
+/// tables have the tables with new names appended. Any other value which is different is a
+/// conflict, unless `overwrite` is set.
+fn merge_item(path: &str, existing: &mut Item, new: &Item, overwrite: bool) -> error::Result<()> {
+    if existing.is_none() {
+        *existing = new.clone();
+        return Ok(());
+    }
+
+    if let (Some(existing), Some(new)) = (existing.as_table_like_mut(), new.as_table_like()) {
+        for (key, item) in new.iter() {
+            let path = format!("{}.{}", path, key);
+            merge_item(
+                &path,
+                existing.entry(key).or_insert(Item::None),
+                item,
+                overwrite,
+            )?;
+        }
+
+        return Ok(());
+    }
+
+    if let (Some(existing), Some(new)) =
+        (existing.as_array_of_tables_mut(), new.as_array_of_tables())
+    {
+        for table in new.iter() {
+            let name = table.get("name").and_then(Item::as_str);
+            let present = name.is_some()
+                && existing
+                    .iter()
+                    .any(|table| table.get("name").and_then(Item::as_str) == name);
+
+            if !present {
+                existing.push(table.clone());
+            }
+        }
+
+        return Ok(());
+    }
+
+    if let (Some(existing), Some(new)) = (existing.as_array_mut(), new.as_array()) {
+        for value in new.iter() {
+            if !existing.iter().any(|v| same_value(v, value)) {
+                existing.push_formatted(value.clone());
+            }
+        }
+
+        return Ok(());
+    }
+
+    match (existing.as_value(), new.as_value()) {
+        (Some(a), Some(b)) if same_value(a, b) => Ok(()),
+        _ if overwrite => {
+            *existing = new.clone();
+            Ok(())
+        }
+        _ => Err(conflict(path)),
+    }
+}
+
+/// Compares two values, ignoring their formatting.
+fn same_value(a: &Value, b: &Value) -> bool {
+    a.to_string().trim() == b.to_string().trim()
+}
+
+fn conflict(path: &str) -> error::Error {
+    error::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "couldn't merge `{}` into Cargo.toml: it is already set to a different value",
+            path
+        ),
+    )
+    .with_help("edit the Cargo.toml of the template or playground so they agree")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Makes a manifest of a package with `tables` after its `[package]`.
+    fn manifest(tables: &str) -> Manifest {
+        let contents = format!(
+            "[package]\nname = \"playground\"\nversion = \"0.1.0\"\nedition = \"2018\"\n\n{}",
+            tables
+        );
+
+        Manifest {
+            path: PathBuf::from("Cargo.toml"),
+            doc: contents.parse().unwrap(),
+        }
+    }
+
+    fn dep(manifest: &Manifest, name: &str) -> Dep {
+        manifest
+            .dependencies()
+            .into_iter()
+            .find(|dep| dep.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn merges_dependencies() {
+        let mut manifest = manifest(
+            r#"[dependencies]
+serde = { version = "1", features = ["derive"] }
+rand = "*"
+"#,
+        );
+
+        manifest
+            .merge(
+                r#"[dependencies]
+serde = { version = "1", features = ["std"] }
+rand = "0.8"
+regex = "1"
+"#,
+            )
+            .unwrap();
+
+        assert_eq!(dep(&manifest, "serde").features, ["derive", "std"]);
+        assert_eq!(dep(&manifest, "rand").version.as_deref(), Some("0.8"));
+        assert_eq!(dep(&manifest, "regex").version.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn reports_conflicting_dependencies() {
+        let mut manifest = manifest("[dependencies]\nrand = \"0.7\"\n");
+        assert!(manifest.merge("[dependencies]\nrand = \"0.8\"\n").is_err());
+    }
+
+    #[test]
+    fn combines_arrays() {
+        let mut manifest = manifest(
+            r#"[features]
+default = ["a"]
+
+[[bin]]
+name = "one"
+"#,
+        );
+
+        manifest
+            .merge(
+                r#"[features]
+default = ["a", "b"]
+
+[[bin]]
+name = "one"
+
+[[bin]]
+name = "two"
+"#,
+            )
+            .unwrap();
+
+        let default: Vec<_> = manifest.doc["features"]["default"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|value| value.as_str())
+            .collect();
+        assert_eq!(default, ["a", "b"]);
+
+        let bins: Vec<_> = manifest.doc["bin"]
+            .as_array_of_tables()
+            .unwrap()
+            .iter()
+            .filter_map(|bin| bin.get("name")?.as_str())
+            .collect();
+        assert_eq!(bins, ["one", "two"]);
+    }
+
+    #[test]
+    fn overwrites_package_except_name() {
+        let mut manifest = manifest("");
+
+        manifest
+            .merge("[package]\nname = \"template\"\nedition = \"2021\"\n")
+            .unwrap();
+
+        assert_eq!(manifest.package_field("name"), Some("playground"));
+        assert_eq!(manifest.package_field("edition"), Some("2021"));
+    }
+
+    #[test]
+    fn reports_conflicts() {
+        let mut manifest = manifest("[profile.release]\nopt-level = 3\n");
+
+        manifest
+            .merge("[profile.release]\nopt-level = 3\n")
+            .unwrap();
+
+        let err = manifest
+            .merge("[profile.release]\nopt-level = 2\n")
+            .unwrap_err();
+        assert!(err.to_string().contains("profile.release.opt-level"));
+    }
+
+    #[test]
+    fn pins_wildcards() {
+        let mut manifest = manifest(
+            r#"[dependencies]
+rand = "*"
+serde = "1"
+foo = { git = "https://github.com/foo/foo" }
+bar = "*"
+"#,
+        );
+
+        let versions = [("rand", "0.8.5"), ("serde", "1.0.200"), ("foo", "0.1.0")]
+            .iter()
+            .map(|(name, version)| (name.to_string(), version.to_string()))
+            .collect();
+        manifest.pin_wildcards(&versions).unwrap();
+
+        assert_eq!(dep(&manifest, "rand").version.as_deref(), Some("0.8.5"));
+        assert_eq!(dep(&manifest, "serde").version.as_deref(), Some("1"));
+        assert_eq!(dep(&manifest, "foo").version, None);
+        // not resolved, so it is left as it is
+        assert_eq!(dep(&manifest, "bar").version.as_deref(), Some("*"));
+    }
+}
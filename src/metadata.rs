@@ -0,0 +1,162 @@
+use crate::{manifest::Manifest, toolchain};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+use toml_edit::{value, Array, Document, Item};
+
+/// The file the metadata of a playground is stored in, relative to the playground.
+pub const METADATA_FILE: &str = ".playground.toml";
+
+/// What is known about a playground apart from its code, such as where it came from and when it
+/// was last used. Times are in seconds since the unix epoch.
+#[derive(Debug, Default)]
+pub struct Metadata {
+    pub created: Option<u64>,
+    /// The crate or standard library item given to `--template` or `--std-template`
+    pub template: Option<String>,
+    /// The named template given to `--saved-template`
+    pub saved_template: Option<String>,
+    /// The file or project given to `--from`
+    pub from: Option<String>,
+    /// The dependencies the playground was created with
+    pub deps: Vec<String>,
+    pub toolchain: Option<String>,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    /// Whether the playground is kept by `clean`
+    pub pinned: bool,
+    pub last_opened: Option<u64>,
+    pub last_run: Option<u64>,
+}
+
+impl Metadata {
+    /// Reads the metadata of a playground. Playgrounds created before metadata was recorded get
+    /// it filled in from what can be found out about them, which is saved for next time.
+    pub fn load(playground: &Path) -> Self {
+        match fs::read_to_string(playground.join(METADATA_FILE)) {
+            Ok(contents) => Self::parse(&contents),
+            Err(_) => {
+                let metadata = Self::infer(playground);
+                // not being able to save it only means it is inferred again
+                let _ = metadata.save(playground);
+                metadata
+            }
+        }
+    }
+
+    /// Loads the metadata of a playground, changes it with `f` and saves it.
+    pub fn update<F: FnOnce(&mut Self)>(playground: &Path, f: F) -> io::Result<()> {
+        let mut metadata = Self::load(playground);
+        f(&mut metadata);
+        metadata.save(playground)
+    }
+
+    /// Writes the metadata to the playground, keeping any other keys in the file.
+    pub fn save(&self, playground: &Path) -> io::Result<()> {
+        let path = playground.join(METADATA_FILE);
+        let mut doc = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| contents.parse::<Document>().ok())
+            .unwrap_or_default();
+
+        let time = |time: Option<u64>| time.map(|time| value(time as i64));
+        let string = |string: &Option<String>| string.as_deref().map(value);
+
+        set(&mut doc, "created", time(self.created));
+        set(&mut doc, "description", string(&self.description));
+        set(&mut doc, "tags", Some(strings(&self.tags)));
+        set(
+            &mut doc,
+            "pinned",
+            Some(value(true)).filter(|_| self.pinned),
+        );
+        set(&mut doc, "template", string(&self.template));
+        set(&mut doc, "saved-template", string(&self.saved_template));
+        set(&mut doc, "from", string(&self.from));
+        set(&mut doc, "dependencies", Some(strings(&self.deps)));
+        set(&mut doc, "toolchain", string(&self.toolchain));
+        set(&mut doc, "last-opened", time(self.last_opened));
+        set(&mut doc, "last-run", time(self.last_run));
+
+        fs::write(path, doc.to_string())
+    }
+
+    fn parse(contents: &str) -> Self {
+        let doc = contents.parse::<Document>().unwrap_or_default();
+
+        let time = |key: &str| doc.get(key)?.as_integer().map(|time| time as u64);
+        let string = |key: &str| doc.get(key)?.as_str().map(String::from);
+        let list = |key: &str| -> Vec<String> {
+            doc.get(key)
+                .and_then(Item::as_array)
+                .map(|array| array.iter().filter_map(|s| s.as_str()).map(String::from))
+                .into_iter()
+                .flatten()
+                .collect()
+        };
+
+        Metadata {
+            created: time("created"),
+            template: string("template"),
+            saved_template: string("saved-template"),
+            from: string("from"),
+            deps: list("dependencies"),
+            toolchain: string("toolchain"),
+            description: string("description"),
+            tags: list("tags"),
+            pinned: doc
+                .get("pinned")
+                .and_then(Item::as_bool)
+                .unwrap_or_default(),
+            last_opened: time("last-opened"),
+            last_run: time("last-run"),
+        }
+    }
+
+    /// Finds out what it can about a playground without metadata. Where it came from isn't known.
+    fn infer(playground: &Path) -> Self {
+        let manifest = Manifest::open(playground.join("Cargo.toml")).ok();
+
+        // the manifest is created along with the playground, and rarely recreated
+        let created = fs::metadata(playground.join("Cargo.toml"))
+            .and_then(|file| file.created().or_else(|_| file.modified()))
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|time| time.as_secs());
+
+        Metadata {
+            created,
+            deps: manifest
+                .map(|manifest| manifest.dependency_names())
+                .unwrap_or_default(),
+            toolchain: toolchain::read(playground),
+            ..Default::default()
+        }
+    }
+
+    /// Checks whether the playground has all of `tags`.
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|tag| self.tags.contains(tag))
+    }
+}
+
+/// The current time in seconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+fn strings(strings: &[String]) -> Item {
+    value(strings.iter().collect::<Array>())
+}
+
+fn set(doc: &mut Document, key: &str, item: Option<Item>) {
+    match item {
+        Some(item) => doc[key] = item,
+        None => {
+            doc.remove(key);
+        }
+    }
+}
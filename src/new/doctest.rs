@@ -0,0 +1,343 @@
+// Extracting and wrapping code blocks the way rustdoc does for doc tests. See
+// https://doc.rust-lang.org/rustdoc/write-documentation/documentation-tests.html
+
+/// Attributes of a code block which still make it a rust code block.
+const RUST_ATTRS: &[&str] = &["rust", "no_run", "should_panic", "test_harness"];
+/// Attributes of a rust code block which mean it can't be used as is.
+const SKIPPED_ATTRS: &[&str] = &["ignore", "compile_fail"];
+
+pub struct CodeBlock {
+    /// The line (starting from 1) in the documentation at which the code block starts
+    pub line: usize,
+    /// The heading the code block is under, if any
+    pub heading: Option<String>,
+    /// The code with hidden lines included, but not yet wrapped in `fn main`
+    pub code: String,
+}
+
+/// Extracts the `//!` documentation of a source file as markdown. Other lines are kept as empty
+/// lines, so line numbers stay the same.
+pub fn inner_docs(source: &str) -> String {
+    let mut docs = String::with_capacity(source.len());
+
+    for line in source.lines() {
+        if let Some(doc) = line.trim_start().strip_prefix("//!") {
+            docs.push_str(doc.strip_prefix(' ').unwrap_or(doc));
+        }
+        docs.push('\n');
+    }
+
+    docs
+}
+
+/// Extracts the `///` documentation of the item defined on line `item` (starting from 0) of a
+/// source file as markdown, skipping any attributes and comments in between. Like
+/// [`inner_docs`], other lines are kept as empty lines.
+pub fn outer_docs(source: &str, item: usize) -> String {
+    let lines: Vec<_> = source.lines().take(item).collect();
+
+    let mut start = lines.len();
+    // whether the line is inside an attribute spanning multiple lines
+    let mut in_attr = false;
+
+    for (i, line) in lines.iter().enumerate().rev() {
+        let trimmed = line.trim();
+
+        if in_attr {
+            in_attr = !trimmed.starts_with("#[");
+        } else if trimmed.starts_with("///") {
+            start = i;
+        } else if trimmed.starts_with("#[") || trimmed.starts_with("//") {
+            // attributes and comments can be between the documentation and the item
+        } else if trimmed.ends_with(']') {
+            in_attr = true;
+        } else {
+            break;
+        }
+    }
+
+    let mut docs = "\n".repeat(start);
+
+    for line in &lines[start..] {
+        if let Some(doc) = line.trim_start().strip_prefix("///") {
+            docs.push_str(doc.strip_prefix(' ').unwrap_or(doc));
+        }
+        docs.push('\n');
+    }
+
+    docs
+}
+
+/// Extracts the rust code blocks from markdown. If `untagged_is_rust` is set, code blocks without
+/// a language are treated as rust (like in doc comments), otherwise they are skipped (like in
+/// READMEs, where they are often shell commands).
+pub fn code_blocks(markdown: &str, untagged_is_rust: bool) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut heading = None;
+    // (fence character, fence length, line, is_rust, code)
+    let mut current: Option<(char, usize, usize, bool, String)> = None;
+
+    for (i, line) in markdown.lines().enumerate() {
+        let trimmed = line.trim_start();
+
+        if let Some((fence, fence_len, start, is_rust, ref mut code)) = current {
+            let closing = trimmed.trim_end();
+            if closing.len() >= fence_len && closing.chars().all(|c| c == fence) {
+                if is_rust {
+                    blocks.push(CodeBlock {
+                        line: start,
+                        heading: heading.clone(),
+                        code: unhide(code),
+                    });
+                }
+                current = None;
+            } else {
+                code.push_str(line);
+                code.push('\n');
+            }
+
+            continue;
+        }
+
+        let fence = trimmed.chars().next().filter(|&c| c == '`' || c == '~');
+        let fence_len = fence.map_or(0, |fence| {
+            trimmed.chars().take_while(|&c| c == fence).count()
+        });
+
+        if let (Some(fence), true) = (fence, fence_len >= 3) {
+            let info = trimmed[fence_len..].trim();
+            current = Some((
+                fence,
+                fence_len,
+                i + 1,
+                is_rust(info, untagged_is_rust),
+                String::new(),
+            ));
+        } else if trimmed.starts_with('#') {
+            let title = trimmed.trim_start_matches('#').trim();
+            if !title.is_empty() {
+                heading = Some(title.to_owned());
+            }
+        }
+    }
+
+    blocks
+}
+
+fn is_rust(info: &str, untagged_is_rust: bool) -> bool {
+    let attrs: Vec<_> = info
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attr| !attr.is_empty())
+        .collect();
+
+    if attrs.iter().any(|attr| SKIPPED_ATTRS.contains(attr)) {
+        return false;
+    }
+
+    let all_rust = attrs
+        .iter()
+        .all(|attr| RUST_ATTRS.contains(attr) || attr.starts_with("edition"));
+
+    all_rust && (untagged_is_rust || attrs.contains(&"rust"))
+}
+
+/// Includes the hidden lines (starting with `# `) of a code block, and unescapes the lines
+/// starting with `##`.
+fn unhide(code: &str) -> String {
+    let mut unhidden = String::with_capacity(code.len());
+
+    for line in code.lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed == "#" {
+            // an empty hidden line
+        } else if let Some(rest) = trimmed.strip_prefix("##") {
+            unhidden.push_str(&line[..line.len() - trimmed.len()]);
+            unhidden.push('#');
+            unhidden.push_str(rest);
+        } else if let Some(rest) = trimmed.strip_prefix("# ") {
+            unhidden.push_str(rest);
+        } else {
+            unhidden.push_str(line);
+        }
+
+        unhidden.push('\n');
+    }
+
+    unhidden
+}
+
+/// Wraps the code of a doc test in `fn main` if it doesn't have one, keeping crate attributes and
+/// `extern crate`s outside of it.
+pub fn wrap(code: &str) -> String {
+    if code.contains("fn main") {
+        return code.to_owned();
+    }
+
+    let mut prelude = String::new();
+    let mut body = String::new();
+    let mut in_prelude = true;
+
+    for line in code.lines() {
+        let trimmed = line.trim();
+
+        if in_prelude
+            && (trimmed.starts_with("#![")
+                || trimmed.starts_with("#[macro_use]")
+                || trimmed.starts_with("extern crate")
+                || trimmed.is_empty())
+        {
+            prelude.push_str(line);
+            prelude.push('\n');
+            continue;
+        }
+
+        in_prelude = false;
+        if !line.is_empty() {
+            body.push_str("    ");
+        }
+        body.push_str(line);
+        body.push('\n');
+    }
+
+    let prelude = prelude.trim();
+    let body = body.trim_end();
+
+    // Like rustdoc, a trailing `Ok::<..>(())` means that `?` is used in the code
+    let returns_result = body
+        .lines()
+        .last()
+        .is_some_and(|line| line.trim().starts_with("Ok::<"));
+
+    let mut wrapped = String::new();
+
+    if !prelude.is_empty() {
+        wrapped.push_str(prelude);
+        wrapped.push_str("\n\n");
+    }
+
+    if returns_result {
+        wrapped.push_str("fn main() {\n    fn _inner() -> Result<(), impl std::fmt::Debug> {\n");
+        for line in body.lines() {
+            if !line.is_empty() {
+                wrapped.push_str("    ");
+            }
+            wrapped.push_str(line);
+            wrapped.push('\n');
+        }
+        wrapped.push_str("    }\n    _inner().unwrap()\n}\n");
+    } else {
+        wrapped.push_str("fn main() {\n");
+        wrapped.push_str(body);
+        wrapped.push_str("\n}\n");
+    }
+
+    wrapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_hidden_lines() {
+        let blocks = code_blocks(
+            "```\n# use std::io;\n#\nlet x = 1;\n    ## not hidden\n#[derive(Debug)]\n```\n",
+            true,
+        );
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(
+            blocks[0].code,
+            "use std::io;\n\nlet x = 1;\n    # not hidden\n#[derive(Debug)]\n"
+        );
+    }
+
+    #[test]
+    fn skips_blocks_which_arent_usable_rust() {
+        let markdown = "\
+```ignore
+a();
+```
+
+```rust,compile_fail
+b();
+```
+
+```text
+c
+```
+
+~~~no_run
+d();
+~~~
+
+```
+e();
+```
+";
+
+        let code: Vec<_> = code_blocks(markdown, true)
+            .into_iter()
+            .map(|block| block.code)
+            .collect();
+        assert_eq!(code, ["d();\n", "e();\n"]);
+
+        // untagged code blocks in READMEs are often shell commands
+        let code: Vec<_> = code_blocks(markdown, false)
+            .into_iter()
+            .map(|block| block.code)
+            .collect();
+        assert!(code.is_empty());
+    }
+
+    #[test]
+    fn records_lines_and_headings() {
+        let blocks = code_blocks("# Usage\n\n```rust\nfoo();\n```\n", false);
+
+        assert_eq!(blocks[0].line, 3);
+        assert_eq!(blocks[0].heading.as_deref(), Some("Usage"));
+    }
+
+    #[test]
+    fn wraps_in_main() {
+        let code = r#"#![allow(unused)]
+extern crate foo;
+
+let x = 1;
+
+println!("{}", x);
+"#;
+
+        let wrapped = r#"#![allow(unused)]
+extern crate foo;
+
+fn main() {
+    let x = 1;
+
+    println!("{}", x);
+}
+"#;
+        assert_eq!(wrap(code), wrapped);
+
+        let code = "fn main() {\n    foo();\n}\n";
+        assert_eq!(wrap(code), code);
+    }
+
+    #[test]
+    fn wraps_code_returning_results() {
+        let code = r#"let n: u32 = "1".parse()?;
+Ok::<(), std::num::ParseIntError>(())
+"#;
+
+        let wrapped = r#"fn main() {
+    fn _inner() -> Result<(), impl std::fmt::Debug> {
+        let n: u32 = "1".parse()?;
+        Ok::<(), std::num::ParseIntError>(())
+    }
+    _inner().unwrap()
+}
+"#;
+        assert_eq!(wrap(code), wrapped);
+    }
+}
@@ -1,125 +1,388 @@
-use crate::helpers;
-use std::io::BufRead;
-use std::path::PathBuf;
+use super::doctest;
+use crate::{dep::Dep, error, helpers};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::{fs, io};
+use toml_edit::Document;
 
 pub struct Examples {
-    pub path: PathBuf,
-    pub examples: Vec<String>,
+    pub examples: Vec<Example>,
+}
+
+pub struct Example {
+    pub name: String,
+    pub source: Source,
+    /// The features of the crate which must be enabled for the example to compile
+    pub required_features: Vec<String>,
+}
+
+pub enum Source {
+    /// A file containing `main`
+    File(PathBuf),
+    /// The `main.rs` of an example directory (eg: `examples/foo/main.rs`), in which case the other
+    /// files in the directory are part of the example
+    Dir(PathBuf),
+    /// A code block from the documentation of the crate, already wrapped in `fn main`
+    Code(String),
 }
 
 impl Examples {
-    pub fn find(manifest: &mut PathBuf, dep_name: &str) -> io::Result<Option<Examples>> {
-        // make sure that the template dependency is present
-        if !Command::new("cargo")
-            .arg("fetch")
-            .arg("--manifest-path")
-            .arg(&manifest)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()?
-            .success()
-        {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "could not fetch template dependency",
-            ));
-        }
+    /// Finds the examples of the dependency `dep` of the playground with the given manifest. The
+    /// dependency is expected to have been fetched already.
+    pub fn find(manifest: &Path, dep: &Dep, offline: bool) -> error::Result<Option<Examples>> {
+        let crate_root = find_package_root(manifest, dep, offline)?;
 
-        manifest.set_extension("lock");
-        let lock_file = fs::File::open(&manifest)?;
-        manifest.set_extension("toml");
+        let read_err = |err: io::Error| {
+            error::Error::new(
+                err.kind(),
+                format!("couldn't read the examples of {}: {}", dep.name, err),
+            )
+        };
 
-        let mut lines = io::BufReader::new(lock_file).lines();
+        let crate_manifest = fs::read_to_string(crate_root.join("Cargo.toml"))
+            .map_err(read_err)?
+            .parse::<Document>()
+            .map_err(|err| {
+                error::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("couldn't parse the Cargo.toml of {}: {}", dep.name, err),
+                )
+            })?;
 
-        let mut dep = String::with_capacity(dep_name.len() + 2);
-        dep.push('"');
-        dep.push_str(dep_name);
-        dep.push('"');
+        let mut examples = scan_examples(&crate_root, &crate_manifest).map_err(read_err)?;
+        examples.extend(doc_snippets(&crate_root, &crate_manifest));
 
-        loop {
-            let line = lines
-                .next()
-                .expect("didn't find entry for target package")?;
-            if line.starts_with("name") && line.contains(&dep) {
-                break;
-            }
+        if examples.is_empty() {
+            return Ok(None);
         }
 
-        let dep_path = {
-            let line = lines.next().unwrap()?;
+        Ok(Some(Examples { examples }))
+    }
 
-            assert!(line.starts_with("version"));
+    pub fn pick_one(self) -> crossterm::Result<Option<Example>> {
+        let Self { mut examples } = self;
 
-            let start = line.find('"').unwrap() + 1;
-            let end = line.rfind('"').unwrap();
+        let names: Vec<_> = examples
+            .iter()
+            .map(|example| {
+                if example.required_features.is_empty() {
+                    example.name.clone()
+                } else {
+                    format!(
+                        "{} (requires {})",
+                        example.name,
+                        example.required_features.join(", ")
+                    )
+                }
+            })
+            .collect();
 
-            let mut s = String::with_capacity(dep_name.len() + 1 + end - start);
+        let pick = helpers::pick_from("Pick an example:", &names)?;
+        Ok(pick.map(|i| examples.swap_remove(i)))
+    }
+}
 
-            s += dep_name;
-            s.push('-');
-            s += &line[start..end];
+impl Example {
+    /// Copies the example into a playground, with the file containing `main` becoming `entry`.
+    pub fn copy_to(&self, entry: &Path) -> io::Result<()> {
+        let path = match self.source {
+            Source::File(ref path) => return fs::copy(path, entry).map(|_| ()),
+            Source::Code(ref code) => return fs::write(entry, code),
+            Source::Dir(ref path) => path,
+        };
 
-            s
+        let (from, to) = match (path.parent(), entry.parent()) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return fs::copy(path, entry).map(|_| ()),
         };
 
-        let mut examples_path = get_cargo_src_root(&dep_path).map_err(|err| {
-            io::Error::new(err.kind(), format!("couldn't find cargo src root: {}", err))
-        })?;
+        helpers::copy_dir(from, to, &[], None)?;
 
-        examples_path.push("examples");
+        let main = to.join(path.file_name().unwrap_or_default());
+        if main != entry {
+            fs::rename(main, entry)?;
+        }
 
-        if !examples_path.is_dir() {
-            return Ok(None);
+        Ok(())
+    }
+}
+
+/// Lists the examples of the crate at `root`. These are the examples declared with `[[example]]`
+/// in its Cargo.toml, along with the ones cargo discovers automatically in `examples/` (unless
+/// `autoexamples = false`).
+fn scan_examples(root: &Path, manifest: &Document) -> io::Result<Vec<Example>> {
+    let mut examples = Vec::new();
+
+    let declared = manifest
+        .get("example")
+        .and_then(|e| e.as_array_of_tables())
+        .into_iter()
+        .flatten();
+
+    for table in declared {
+        let name = match table.get("name").and_then(|n| n.as_str()) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let path = match table.get("path").and_then(|p| p.as_str()) {
+            Some(path) => root.join(path),
+            None => match discover_example(&root.join("examples"), name) {
+                Some(path) => path,
+                None => continue,
+            },
+        };
+
+        if !path.is_file() {
+            continue;
         }
 
-        let mut examples = Vec::new();
+        let required_features = table
+            .get("required-features")
+            .and_then(|f| f.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|f| f.as_str().map(String::from))
+            .collect();
+
+        examples.push(Example {
+            name: name.to_owned(),
+            source: file_source(path),
+            required_features,
+        });
+    }
+
+    let autoexamples = manifest
+        .get("package")
+        .and_then(|p| p.get("autoexamples"))
+        .and_then(|a| a.as_bool())
+        .unwrap_or(true);
 
-        for example in examples_path.read_dir()?.filter_map(Result::ok) {
-            if let Ok(example) = example.file_name().into_string() {
-                if example.ends_with(".rs") {
-                    examples.push(example.to_owned());
+    let examples_dir = root.join("examples");
+
+    if autoexamples && examples_dir.is_dir() {
+        for entry in examples_dir.read_dir()?.flatten() {
+            let path = entry.path();
+
+            let (name, path) = if path.is_dir() {
+                (entry.file_name(), path.join("main.rs"))
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                match path.file_stem() {
+                    Some(stem) => (stem.to_owned(), path.clone()),
+                    None => continue,
                 }
+            } else {
+                continue;
+            };
+
+            let name = match name.into_string() {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+
+            // declared examples take precedence over the discovered ones with the same name
+            let declared = examples.iter().any(|e| match e.source {
+                Source::File(ref p) | Source::Dir(ref p) => e.name == name || *p == path,
+                Source::Code(_) => false,
+            });
+
+            if !path.is_file() || declared {
+                continue;
             }
-        }
 
-        if examples.is_empty() {
-            return Ok(None);
+            examples.push(Example {
+                name,
+                source: file_source(path),
+                required_features: Vec::new(),
+            });
         }
+    }
+
+    examples.sort_unstable_by(|a, b| a.name.cmp(&b.name));
 
-        Ok(Some(Examples {
-            path: examples_path,
-            examples,
-        }))
+    Ok(examples)
+}
+
+/// Finds the file for an example declared without a path, the same way cargo does.
+fn discover_example(examples_dir: &Path, name: &str) -> Option<PathBuf> {
+    let file = examples_dir.join(format!("{}.rs", name));
+    if file.is_file() {
+        return Some(file);
     }
 
-    pub fn pick_one(self) -> crossterm::Result<Option<PathBuf>> {
-        let Self { examples, mut path } = self;
-        let pick = helpers::pick_from("Pick an example:", &examples)?;
-        Ok(pick.map(|i| {
-            path.push(&examples[i]);
-            path
-        }))
+    let dir = examples_dir.join(name).join("main.rs");
+    if dir.is_file() {
+        return Some(dir);
+    }
+
+    None
+}
+
+/// Checks whether the example is the `main.rs` of a directory in `examples/`, and so may have other
+/// files.
+fn file_source(path: PathBuf) -> Source {
+    let is_dir = path.file_name().is_some_and(|f| f == "main.rs")
+        && path
+            .parent()
+            .and_then(Path::parent)
+            .and_then(Path::file_name)
+            .is_some_and(|dir| dir == "examples");
+
+    if is_dir {
+        Source::Dir(path)
+    } else {
+        Source::File(path)
     }
 }
 
-fn get_cargo_src_root(dep_path: &str) -> io::Result<PathBuf> {
-    let mut cargo_home = home::cargo_home()?;
+/// Takes the rust code blocks in the README and crate level documentation of the crate as
+/// examples, wrapped in `fn main` like rustdoc does for doc tests.
+fn doc_snippets(root: &Path, manifest: &Document) -> Vec<Example> {
+    let package = manifest.get("package");
+
+    // `readme = false` means there is no README
+    let readme = match package.and_then(|p| p.get("readme")) {
+        Some(readme) => readme.as_str().map(String::from),
+        None => ["README.md", "README.txt", "README"]
+            .iter()
+            .find(|readme| root.join(readme).is_file())
+            .map(|readme| readme.to_string()),
+    };
+
+    let lib = manifest
+        .get("lib")
+        .and_then(|lib| lib.get("path"))
+        .and_then(|path| path.as_str())
+        .unwrap_or("src/lib.rs");
+
+    let mut sources = Vec::new();
+
+    if let Some(readme) = readme {
+        if let Ok(contents) = fs::read_to_string(root.join(&readme)) {
+            sources.push((readme, doctest::code_blocks(&contents, false)));
+        }
+    }
+
+    if let Ok(contents) = fs::read_to_string(root.join(lib)) {
+        sources.push((
+            lib.to_owned(),
+            doctest::code_blocks(&doctest::inner_docs(&contents), true),
+        ));
+    }
+
+    let mut snippets = Vec::new();
+
+    for (file, blocks) in sources {
+        // The crate docs are often the README included with `#![doc = include_str!(..)]`
+        push_code_blocks(&mut snippets, &file, blocks);
+    }
+
+    snippets
+}
 
-    cargo_home.push("registry");
-    cargo_home.push("src");
+/// Adds code blocks from the documentation in `file` as examples, wrapped in `fn main` like rustdoc
+/// does for doc tests. Code blocks identical to an existing example are skipped.
+pub fn push_code_blocks(examples: &mut Vec<Example>, file: &str, blocks: Vec<doctest::CodeBlock>) {
+    for block in blocks {
+        let code = doctest::wrap(&block.code);
 
-    for dir in cargo_home.read_dir()?.flat_map(Result::ok) {
-        let mut path = dir.path();
-        path.push(dep_path);
-        if path.exists() {
-            return Ok(path);
+        if examples
+            .iter()
+            .any(|e| matches!(e.source, Source::Code(ref c) if *c == code))
+        {
+            continue;
         }
+
+        let name = match block.heading {
+            Some(heading) => format!("{}:{} ({})", file, block.line, heading),
+            None => format!("{}:{}", file, block.line),
+        };
+
+        examples.push(Example {
+            name,
+            source: Source::Code(code),
+            required_features: Vec::new(),
+        });
+    }
+}
+
+/// Finds the root directory of the package that `dep` resolved to, using `cargo metadata`. This
+/// works for any kind of source (registry, git, path or vendored).
+fn find_package_root(manifest: &Path, dep: &Dep, offline: bool) -> error::Result<PathBuf> {
+    let mut cmd = Command::new("cargo");
+    cmd.args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(manifest);
+
+    if offline {
+        cmd.arg("--offline");
+    }
+
+    let output = cmd.stderr(Stdio::piped()).output()?;
+
+    if !output.status.success() {
+        return Err(error::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "`cargo metadata` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
     }
 
-    Err(io::Error::new(
-        io::ErrorKind::NotFound,
-        cargo_home.to_str().unwrap_or("src is empty"),
-    ))
+    let invalid = |what: &str| {
+        error::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("unexpected output from `cargo metadata`: {}", what),
+        )
+    };
+
+    let metadata: Value =
+        serde_json::from_slice(&output.stdout).map_err(|err| invalid(&err.to_string()))?;
+
+    let root = metadata["resolve"]["root"]
+        .as_str()
+        .ok_or_else(|| invalid("missing resolve root"))?;
+
+    let node = metadata["resolve"]["nodes"]
+        .as_array()
+        .and_then(|nodes| nodes.iter().find(|node| node["id"] == root))
+        .ok_or_else(|| invalid("missing resolve node for the playground"))?;
+
+    let packages = metadata["packages"]
+        .as_array()
+        .ok_or_else(|| invalid("missing packages"))?;
+    let package = |id: &str| packages.iter().find(|package| package["id"] == id);
+
+    let deps = node["deps"]
+        .as_array()
+        .ok_or_else(|| invalid("missing dependencies of the playground"))?;
+
+    // The name in the resolve graph is the one the dependency is imported with, which is what
+    // distinguishes renamed dependencies from each other
+    let extern_name = dep.name.replace('-', "_");
+    let dep_package = deps
+        .iter()
+        .find(|d| d["name"] == extern_name.as_str())
+        .and_then(|d| package(d["pkg"].as_str()?))
+        .or_else(|| {
+            deps.iter()
+                .filter_map(|d| package(d["pkg"].as_str()?))
+                .find(|p| p["name"] == dep.package_name())
+        })
+        .ok_or_else(|| {
+            error::Error::new(
+                io::ErrorKind::NotFound,
+                format!("couldn't find {} in the resolved dependencies", dep.name),
+            )
+        })?;
+
+    let manifest_path = dep_package["manifest_path"]
+        .as_str()
+        .ok_or_else(|| invalid("missing manifest path of the template dependency"))?;
+
+    Path::new(manifest_path)
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| invalid("invalid manifest path of the template dependency"))
 }
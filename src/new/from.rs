@@ -0,0 +1,92 @@
+use crate::{
+    error, helpers, kind::Kind, manifest::Manifest, metadata::METADATA_FILE, open::OPEN_LOCK,
+    script::Script,
+};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// Files and directories in a project which are not copied into the playground.
+const IGNORED: &[&str] = &["target", ".git", METADATA_FILE, OPEN_LOCK];
+
+/// What a playground can be created from with `--from`.
+pub enum Base {
+    /// An existing cargo project
+    Project(PathBuf),
+    /// A single rust file, which can have a cargo-script style embedded manifest
+    File(Script),
+}
+
+/// Reads the base of a playground, which is either a path to a cargo project or rust file, or `-`
+/// to read the file from stdin.
+pub fn read(from: &str) -> error::Result<Base> {
+    if from == "-" {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source).map_err(|err| {
+            error::Error::new(err.kind(), format!("couldn't read from stdin: {}", err))
+        })?;
+
+        return Ok(Base::File(Script::parse(&source)));
+    }
+
+    let path = Path::new(from);
+
+    if path.is_dir() {
+        if !path.join("Cargo.toml").is_file() {
+            return Err(error::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{:?} is not a cargo project", path),
+            )
+            .with_help("directories must contain a Cargo.toml, otherwise pass a rust file"));
+        }
+
+        // the path has to be absolute for the path dependencies of the project to be resolved
+        return Ok(Base::Project(path.canonicalize()?));
+    }
+
+    let source = fs::read_to_string(path).map_err(|err| {
+        error::Error::new(err.kind(), format!("couldn't read {:?}: {}", path, err))
+    })?;
+
+    Ok(Base::File(Script::parse(&source)))
+}
+
+/// Replaces a freshly created playground with a copy of `project`. The package is renamed to the
+/// playground, and the paths of its path dependencies are made absolute so they still resolve.
+pub fn copy_project(project: &Path, playground: &Path, name: &str) -> error::Result<Manifest> {
+    fs::remove_dir_all(playground)?;
+    helpers::copy_dir(project, playground, IGNORED, None)?;
+
+    let mut manifest = Manifest::open(playground.join("Cargo.toml"))?;
+
+    if manifest.package_field("name").is_none() {
+        return Err(error::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{:?} doesn't have a package", project),
+        )
+        .with_help(
+            "virtual workspaces can't be used as a playground, pass one of its members instead",
+        ));
+    }
+
+    manifest.set_package_field("name", name)?;
+    manifest.absolutize_dep_paths(project);
+
+    if manifest.playground_metadata("kind").is_none() {
+        Kind::detect(playground).record(&mut manifest)?;
+    }
+
+    Ok(manifest)
+}
+
+/// Writes a rust file to the entry of the playground, and merges its embedded manifest (if any)
+/// into the playground's manifest.
+pub fn write_file(script: &Script, entry: &Path, manifest: &mut Manifest) -> error::Result<()> {
+    fs::write(entry, &script.code)?;
+
+    if let Some(ref fragment) = script.manifest {
+        manifest.merge(fragment)?;
+    }
+
+    Ok(())
+}
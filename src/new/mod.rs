@@ -1,18 +1,29 @@
-use crate::{error, helpers, open};
-use std::io::Write;
+use crate::{
+    dep::Dep, error, helpers, infer, kind::Kind, lockfile, manifest::Manifest, metadata,
+    metadata::Metadata, open, registry, tags, templates, toolchain,
+};
+use std::io;
+use std::path::Path;
 use std::process::{Command, Stdio};
-use std::sync::{atomic, Arc};
-use std::time::SystemTime;
-use std::{fmt, fs, io};
 use structopt::StructOpt;
 
+mod doctest;
 mod examples;
+mod from;
+pub mod names;
+pub mod staging;
+mod std_docs;
 
 use examples::Examples;
+use from::Base;
+use staging::Staging;
+
+const EDITIONS: &[&str] = &["2015", "2018", "2021", "2024"];
 
 #[derive(StructOpt, Debug)]
 pub struct NewOpts {
-    /// The name of the playground to create. If not supplied, the current timestamp will be used
+    /// The name of the playground to create. If not supplied, a name like `brave-otter-1` is
+    /// generated
     #[structopt(short, long)]
     name: Option<String>,
     #[structopt(flatten)]
@@ -23,84 +34,163 @@ pub struct NewOpts {
     /// Indicates the editor is a gui editor
     #[structopt(short, long)]
     gui: bool,
+    /// The kind of playground to create. Libraries and tests are run with `cargo test`, and
+    /// benches with `cargo bench`
+    #[structopt(short, long, default_value = "bin", possible_values = Kind::VARIANTS)]
+    kind: Kind,
+    /// The Rust edition of the playground. If not provided, the default of `cargo new` is used
+    #[structopt(long, possible_values = EDITIONS)]
+    edition: Option<String>,
+    /// The toolchain to pin the playground to, eg: `nightly` or `1.56.0`. It is written to a
+    /// rust-toolchain.toml in the playground
+    #[structopt(long)]
+    toolchain: Option<String>,
+    /// Do not access the network, resolving dependencies only from the local registry cache.
+    ///
+    /// Even without this, the local registry cache is used if the network cannot be reached
+    #[structopt(long)]
+    offline: bool,
+    /// Keep wildcard (`*`) dependency versions as they are, instead of pinning them to the
+    /// versions that were resolved when creating the playground
+    #[structopt(long)]
+    float: bool,
+    /// A named template to create the playground from. Templates are stored in the directory
+    /// given by CARGO_PLAYGROUND_TEMPLATES_DIR, and can be created with `save-template`
+    #[structopt(short = "T", long)]
+    saved_template: Option<String>,
     /// The library to base main.rs on. If not provided, base Cargo main.rs will be used.
     ///
+    /// The examples of the library, and the rust code blocks in its README and crate documentation
+    /// can be picked from.
+    ///
     /// Follows same format as dependencies. You do not need to repeat the library in dependencies,
     /// as it is automatically added
     #[structopt(short, long)]
     template: Option<String>,
+    /// A module or item in the standard library to take the examples in its documentation from as
+    /// a base for main.rs, eg: `std::collections::BTreeMap`. This requires the rust-src component
+    #[structopt(long, conflicts_with = "template")]
+    std_template: Option<String>,
+    /// Create the playground from an existing file, cargo project or `-` for stdin. Files become
+    /// the entry of the playground, and their dependencies can be given in a cargo-script style
+    /// embedded manifest. Projects are copied as is, without their target directory
+    #[structopt(long, conflicts_with_all = &["template", "std-template", "saved-template"])]
+    from: Option<String>,
+    /// A tag for the playground, which can be used to find it with `ls --tag` and `open --tag`.
+    /// Can be given multiple times
+    #[structopt(long = "tag", number_of_values = 1)]
+    tags: Vec<String>,
+    /// A description of what the playground is for
+    #[structopt(long = "desc")]
+    description: Option<String>,
     /// The dependencies to add. It must be in the following format:
-    /// 1. <dep-name>
-    /// 2. <dep-name>=<dep-version>
+    /// <dep-name>[@<dep-version>][+<features>][{<key>=<value>,...}]
+    ///
+    /// eg: `rand`, `rand@0.8`, `serde@1+derive`, `tokio@1+full,-default`,
+    /// `foo{git=https://github.com/foo/foo,branch=dev}`. `-default` disables the default features,
+    /// and the supported keys are version, git, branch, tag, rev, path, package, default-features
+    /// and optional. The older `<dep-name>=<dep-version>` format is also accepted.
     #[structopt(name = "dependencies")]
     deps: Vec<String>,
 }
 
 pub fn new(opts: NewOpts) -> error::Result<()> {
     let name = match opts.name {
-        Some(name) => name,
-        None => {
-            let time = SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .map_err(|_| {
-                    error::Error::new(io::ErrorKind::Other, "current time is unix epoch!")
-                })?;
+        Some(name) => {
+            names::validate_name(&name)?;
+
+            if helpers::get_dir().join(&name).exists() {
+                return Err(error::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("playground '{}' already exists", name),
+                )
+                .with_help(format!(
+                    "open it with `cargo playground open {}`, or choose a different name",
+                    name
+                )));
+            }
 
-            format!("playground-{}", time.as_secs())
+            name
         }
+        None => names::generate(&helpers::get_dir()),
     };
 
+    for tag in &opts.tags {
+        tags::validate_tag(tag)?;
+    }
+
+    let base = opts.from.as_deref().map(from::read).transpose()?;
+
+    let mut deps = opts
+        .deps
+        .iter()
+        .map(|dep| Dep::try_parse(dep))
+        .collect::<error::Result<Vec<_>>>()?;
+
     helpers::print_status("Creating", &name);
     println!();
 
-    let mut path = helpers::get_dir();
-    path.push(&name); // Now represents the playground directory
+    // The playground is built in a staging directory, which is removed on any failure or when
+    // picking a template is cancelled
+    let staging = Staging::new(&helpers::get_dir(), &name);
+    let path = staging.path();
 
-    if !Command::new("cargo")
-        .arg("new")
-        .arg(&path)
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .status()?
-        .success()
-    {
-        return Err(error::Error::new(
-            io::ErrorKind::Other,
-            "could not create cargo project",
-        ));
+    cargo_new(path, &name, opts.kind, opts.offline)?;
+
+    let mut manifest = match base {
+        Some(Base::Project(ref project)) => from::copy_project(project, path, &name)?,
+        _ => {
+            let mut manifest = Manifest::open(path.join("Cargo.toml"))?;
+            opts.kind.scaffold(path, &mut manifest)?;
+            manifest
+        }
+    };
+
+    if let Some(Base::File(ref script)) = base {
+        from::write_file(script, &path.join(opts.kind.entry()), &mut manifest)?;
     }
 
-    path.push("Cargo.toml"); // Now represents path to Cargo.toml
-    let mut cargo_toml = fs::OpenOptions::new().append(true).open(&path)?;
+    if let Some(ref template) = opts.saved_template {
+        templates::apply(template, path, &name, &mut manifest)?;
+    }
 
-    if let Some(ref template) = opts.template {
-        let stop = Arc::new(false.into());
-        let loader = helpers::loader("fetching examples", Arc::clone(&stop));
+    if let Some(ref edition) = opts.edition {
+        manifest.set_package_field("edition", edition)?;
+    }
 
-        let dep = Dep::try_parse(&template)?;
-        writeln!(cargo_toml, "{}", dep)?;
+    if let Some(ref channel) = opts.toolchain {
+        toolchain::write(path, channel)?;
+    }
 
-        cargo_toml.flush()?;
+    // once the network is found to be unreachable, there is no point in trying it again
+    let mut offline = opts.offline;
 
-        let examples = Examples::find(&mut path, dep.dep_name)
-            .map_err(|e| error::Error::new(e.kind(), format!("couldn't get templates: {}", e)))?
-            .ok_or_else(|| {
-                error::Error::new(
-                    io::ErrorKind::NotFound,
-                    format!("couldn't find any templates for {}", dep.dep_name),
-                )
-                .with_help(
-                    "templates are taken from the `examples` directory in a crate
-       check if the crate has an examples directory",
-                )
-            });
+    let examples = if let Some(ref template) = opts.template {
+        let dep = Dep::try_parse(template)?;
+        manifest.add_dep(&dep)?;
+        manifest.save()?;
+
+        offline = registry::fetch(manifest.path(), &[&dep], offline)?;
 
-        // only one writer, and reader doesn't care about the race, so its fine
-        stop.store(true, atomic::Ordering::Relaxed);
-        let _ = loader.join();
+        let examples = Examples::find(manifest.path(), &dep, offline)?.ok_or_else(|| {
+            error::Error::new(
+                io::ErrorKind::NotFound,
+                format!("couldn't find any templates for {}", dep.name),
+            )
+            .with_help(
+                "templates are taken from the examples, README and crate documentation of a crate
+       check if the crate has examples or rust code blocks in its documentation",
+            )
+        });
 
-        path.pop();
+        Some((Some(dep), examples))
+    } else {
+        opts.std_template
+            .as_ref()
+            .map(|item| (None, std_docs::find(item, path)))
+    };
 
+    if let Some((dep, examples)) = examples {
         match examples {
             Ok(examples) => {
                 let example = examples.pick_one().map_err(|err| {
@@ -110,35 +200,76 @@ pub fn new(opts: NewOpts) -> error::Result<()> {
                     )
                 })?;
 
-                if example.is_none() {
-                    return Ok(());
-                }
+                let example = match example {
+                    Some(example) => example,
+                    None => return Ok(()),
+                };
 
-                path.push("src");
-                path.push("main.rs");
+                if let (Some(mut dep), false) = (dep, example.required_features.is_empty()) {
+                    dep.features = example.required_features.clone();
+                    manifest.add_dep(&dep)?;
+                }
 
-                fs::copy(&example.unwrap(), &path)?;
+                example.copy_to(&path.join(opts.kind.entry()))?;
             }
             Err(err) => {
                 eprintln!("{}", err);
                 match helpers::pick_from("Do you want to continue anyway?", &["Yes", "No"]) {
                     // Selected 'Yes'
                     Ok(Some(0)) => {}
-                    _ => {
-                        fs::remove_dir_all(path)?;
-                        return Ok(());
-                    }
+                    _ => return Ok(()),
                 }
             }
         }
     }
 
-    for dep in opts.deps {
-        writeln!(cargo_toml, "{}", Dep::try_parse(&dep)?)?;
+    for dep in &deps {
+        manifest.add_dep(dep)?;
+    }
+
+    // code from elsewhere often uses crates which it doesn't declare
+    if base.is_some() {
+        for dep in infer::offer(path, &manifest)? {
+            manifest.add_dep(&dep)?;
+            deps.push(dep);
+        }
+    }
+
+    manifest.save()?;
+
+    if base.is_some() || opts.saved_template.is_some() || !deps.is_empty() {
+        registry::fetch(manifest.path(), &deps.iter().collect::<Vec<_>>(), offline)?;
     }
 
+    let resolved = base.is_some()
+        || opts.template.is_some()
+        || opts.saved_template.is_some()
+        || !deps.is_empty();
+
+    if !opts.float && resolved {
+        let versions = lockfile::resolved_versions(&manifest.path().with_extension("lock"), &name)?;
+        manifest.pin_wildcards(&versions)?;
+        manifest.save()?;
+    }
+
+    let metadata = Metadata {
+        created: Some(metadata::now()),
+        template: opts.template.or(opts.std_template),
+        saved_template: opts.saved_template,
+        from: opts.from,
+        deps: manifest.dependency_names(),
+        toolchain: opts.toolchain,
+        description: opts.description,
+        tags: opts.tags,
+        ..Default::default()
+    };
+    metadata.save(path)?;
+
+    staging.commit(&helpers::get_dir().join(&name))?;
+
     open::open(open::OpenOpts {
-        name,
+        name: Some(name),
+        tags: Vec::new(),
         gui: opts.gui,
         no_w: opts.no_w,
         skip_check: true,
@@ -146,31 +277,35 @@ pub fn new(opts: NewOpts) -> error::Result<()> {
     })
 }
 
-struct Dep<'a> {
-    dep_name: &'a str,
-    dep_ver: &'a str,
-}
+/// Creates the cargo project of a playground called `name` at `path`, reporting why cargo failed
+/// if it does.
+pub fn cargo_new(path: &Path, name: &str, kind: Kind, offline: bool) -> error::Result<()> {
+    let mut cargo_new = Command::new("cargo");
+    cargo_new
+        .arg("new")
+        .arg(kind.cargo_new_flag())
+        .arg("--name")
+        .arg(name)
+        .arg(path);
 
-impl<'a> Dep<'a> {
-    fn try_parse(dep: &'a str) -> error::Result<Self> {
-        let mut parts = dep.split('=');
-        let dep_name = parts.next().unwrap().trim();
-        let dep_ver = parts.next().unwrap_or("*").trim();
+    if offline {
+        cargo_new.arg("--offline");
+    }
 
-        if parts.next().is_some() {
-            return Err(error::Error::new(
-                io::ErrorKind::InvalidInput,
-                format!("dependency '{}' is in an incorrect format", dep),
-            )
-            .with_help("dependencies must either be '<dep-name>' or '<dep-name>=<dep-version>'"));
-        }
+    let output = cargo_new.stdout(Stdio::null()).output()?;
 
-        Ok(Self { dep_name, dep_ver })
-    }
-}
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // cargo prints what it is creating before the error, which only repeats the status above
+        let cause = stderr
+            .find("error: ")
+            .map_or(&*stderr, |start| &stderr[start + "error: ".len()..]);
 
-impl fmt::Display for Dep<'_> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} = \"{}\"", self.dep_name, self.dep_ver)
+        return Err(error::Error::new(
+            io::ErrorKind::Other,
+            format!("could not create cargo project: {}", cause.trim_end()),
+        ));
     }
+
+    Ok(())
 }
@@ -0,0 +1,119 @@
+use crate::error;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::path::Path;
+
+const ADJECTIVES: &[&str] = &[
+    "brave", "calm", "clever", "cosy", "curious", "eager", "fancy", "gentle", "glad", "happy",
+    "jolly", "keen", "lively", "lucky", "merry", "mighty", "nimble", "proud", "quick", "quiet",
+    "rapid", "shiny", "silly", "snappy", "sturdy", "sunny", "swift", "tidy", "witty", "zesty",
+];
+
+const NOUNS: &[&str] = &[
+    "badger", "beetle", "crab", "falcon", "ferret", "gecko", "heron", "koala", "lemur", "lynx",
+    "marmot", "meerkat", "moose", "newt", "otter", "owl", "panda", "parrot", "puffin", "quokka",
+    "rabbit", "raven", "seal", "sloth", "squid", "stoat", "tapir", "toad", "walrus", "wombat",
+];
+
+/// Generates a name for a playground which is easier to remember than a timestamp, like
+/// `brave-otter-1`. The counter is increased until the name isn't taken in `dir`.
+pub fn generate(dir: &Path) -> String {
+    // std doesn't have a random number generator, but its hashers are randomly seeded
+    let random = RandomState::new().build_hasher().finish() as usize;
+
+    let adjective = ADJECTIVES[random % ADJECTIVES.len()];
+    let noun = NOUNS[random / ADJECTIVES.len() % NOUNS.len()];
+
+    (1..)
+        .map(|counter| format!("{}-{}-{}", adjective, noun, counter))
+        .find(|name| !dir.join(name).exists())
+        .unwrap_or_default()
+}
+
+/// Names which cargo doesn't allow for packages, as they are keywords or built-in crates.
+const RESERVED_NAMES: &[&str] = &[
+    "as",
+    "async",
+    "await",
+    "break",
+    "const",
+    "continue",
+    "crate",
+    "dyn",
+    "else",
+    "enum",
+    "extern",
+    "false",
+    "fn",
+    "for",
+    "if",
+    "impl",
+    "in",
+    "let",
+    "loop",
+    "match",
+    "mod",
+    "move",
+    "mut",
+    "pub",
+    "ref",
+    "return",
+    "self",
+    "Self",
+    "static",
+    "struct",
+    "super",
+    "trait",
+    "true",
+    "type",
+    "unsafe",
+    "use",
+    "where",
+    "while",
+    "abstract",
+    "become",
+    "box",
+    "do",
+    "final",
+    "macro",
+    "override",
+    "priv",
+    "try",
+    "typeof",
+    "unsized",
+    "virtual",
+    "yield",
+    "alloc",
+    "core",
+    "proc_macro",
+    "proc-macro",
+    "std",
+    "test",
+];
+
+/// Checks that a playground can be given `name`. It is used as both the directory of the
+/// playground and the name of its package, so it has to be a valid package name.
+pub fn validate_name(name: &str) -> error::Result<()> {
+    let problem = if name.is_empty() {
+        "it is empty"
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        "it starts with a digit"
+    } else if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        // this also keeps names from being paths, like `../foo`
+        "it can only contain letters, digits, `-` and `_`"
+    } else if RESERVED_NAMES.contains(&name) {
+        "it is a keyword or the name of a built-in crate"
+    } else {
+        return Ok(());
+    };
+
+    Err(error::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("invalid playground name '{}', {}", name, problem),
+    )
+    .with_help("playground names are also used as package names, eg: `parse-dates`"))
+}
@@ -0,0 +1,56 @@
+use crate::error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A directory in which a playground is built before it is moved into place, so that a failed or
+/// cancelled `new` or `import` leaves nothing behind. The directory is removed when this is
+/// dropped, unless it was committed.
+pub struct Staging {
+    path: PathBuf,
+    committed: bool,
+}
+
+impl Staging {
+    /// Reserves the staging directory of the playground `name` in `dir`. It is hidden, so it isn't
+    /// listed as a playground while it is being built. The directory itself is left to `cargo new`
+    /// to create, after removing one which was left behind by an interrupted `new` or `import`.
+    pub fn new(dir: &Path, name: &str) -> Self {
+        let path = dir.join(format!(".staging-{}", name));
+        let _ = fs::remove_dir_all(&path);
+
+        Staging {
+            path,
+            committed: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Moves the built playground to `to`.
+    pub fn commit(mut self, to: &Path) -> error::Result<()> {
+        // the playground may have been created elsewhere while this one was being built
+        if to.exists() {
+            return Err(error::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("playground at {:?} was created while building it", to),
+            )
+            .with_help("choose a different name"));
+        }
+
+        fs::rename(&self.path, to)?;
+        self.committed = true;
+
+        Ok(())
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+}
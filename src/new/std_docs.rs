@@ -0,0 +1,230 @@
+use super::doctest;
+use super::examples::{self, Examples};
+use crate::error;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{fs, io};
+
+/// The crates of the standard library, in the order they are searched. Most of `std` is re-exported
+/// from `alloc` and `core`, so they are searched for items in `std` as well.
+const CRATES: &[&str] = &["std", "alloc", "core"];
+
+const PATH_HELP: &str = "the path should be to a module or item in std, alloc or core
+       eg: `std::collections::BTreeMap`, `std::sync::mpsc` or `std::vec::Vec::retain`";
+
+/// Finds the examples in the documentation of a module or item of the standard library (eg:
+/// `std::collections::BTreeMap` or `std::sync::mpsc`). A method of a type can also be given (eg:
+/// `std::vec::Vec::retain`).
+///
+/// The documentation is read from the source in the rust-src component of the toolchain used by
+/// the playground, so this works offline.
+pub fn find(item: &str, playground: &Path) -> error::Result<Examples> {
+    let mut segments: Vec<_> = item.split("::").map(str::trim).collect();
+
+    if segments.iter().any(|segment| segment.is_empty()) {
+        return Err(error::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid path", item),
+        )
+        .with_help(PATH_HELP));
+    }
+
+    let crates = match CRATES.iter().position(|krate| *krate == segments[0]) {
+        // items in std can be defined in any of the crates
+        Some(0) => {
+            segments.remove(0);
+            CRATES
+        }
+        Some(i) => {
+            segments.remove(0);
+            &CRATES[i..=i]
+        }
+        None => CRATES,
+    };
+
+    let library = library_dir(playground)?;
+    let mut found = false;
+
+    for krate in crates {
+        let src = library.join(krate).join("src");
+
+        let (file, docs) = match find_docs(&src, &segments)? {
+            Some(docs) => docs,
+            None => continue,
+        };
+        found = true;
+
+        let file = file.strip_prefix(&library).unwrap_or(&file);
+
+        let mut examples = Vec::new();
+        examples::push_code_blocks(
+            &mut examples,
+            &file.to_string_lossy(),
+            doctest::code_blocks(&docs, true),
+        );
+
+        if !examples.is_empty() {
+            return Ok(Examples { examples });
+        }
+    }
+
+    if found {
+        Err(error::Error::new(
+            io::ErrorKind::NotFound,
+            format!(
+                "couldn't find any examples in the documentation of {}",
+                item
+            ),
+        ))
+    } else {
+        Err(error::Error::new(
+            io::ErrorKind::NotFound,
+            format!("couldn't find {} in the standard library", item),
+        )
+        .with_help(PATH_HELP))
+    }
+}
+
+/// Finds the source of the standard library for the toolchain used by the playground.
+fn library_dir(playground: &Path) -> error::Result<PathBuf> {
+    // rustup picks the toolchain based on the current directory
+    let output = Command::new("rustc")
+        .args(["--print", "sysroot"])
+        .current_dir(playground)
+        .output()?;
+
+    if !output.status.success() {
+        return Err(error::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "couldn't find the sysroot of the toolchain: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        ));
+    }
+
+    let sysroot = String::from_utf8_lossy(&output.stdout);
+    let library = Path::new(sysroot.trim()).join("lib/rustlib/src/rust/library");
+
+    if !library.is_dir() {
+        return Err(error::Error::new(
+            io::ErrorKind::NotFound,
+            "couldn't find the source of the standard library",
+        )
+        .with_help("install it with `rustup component add rust-src`"));
+    }
+
+    Ok(library)
+}
+
+/// Finds the documentation of the module or item at `segments` in the crate with the source `src`.
+/// Returns the file it is in, along with the documentation.
+fn find_docs(src: &Path, segments: &[&str]) -> io::Result<Option<(PathBuf, String)>> {
+    let mut module = src.join("lib.rs");
+    let mut dir = src.to_owned();
+    let mut rest = segments;
+
+    // Follow the modules as far as they go
+    while let Some((segment, others)) = rest.split_first() {
+        let mod_rs = dir.join(segment).join("mod.rs");
+        let file = dir.join(format!("{}.rs", segment));
+
+        module = if mod_rs.is_file() {
+            mod_rs
+        } else if file.is_file() {
+            file
+        } else {
+            break;
+        };
+
+        dir.push(segment);
+        rest = others;
+    }
+
+    if !module.is_file() {
+        return Ok(None);
+    }
+
+    match *rest {
+        [] => {
+            let docs = doctest::inner_docs(&fs::read_to_string(&module)?);
+            Ok(Some((module, docs)))
+        }
+        [item] => find_item(&[module], &dir, item, false),
+        [item, method] => {
+            let (file, _) = match find_item(&[module.clone()], &dir, item, false)? {
+                Some(item) => item,
+                None => return Ok(None),
+            };
+
+            // methods are usually in the same file as the type
+            find_item(&[file, module], &dir, method, true)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Finds the definition of `name` in `files`, or else in any file in `dir`. Returns the file it is
+/// in, along with its documentation.
+fn find_item(
+    files: &[PathBuf],
+    dir: &Path,
+    name: &str,
+    is_method: bool,
+) -> io::Result<Option<(PathBuf, String)>> {
+    // Methods of traits and trait impls aren't `pub`, but other items must be
+    let definition = Regex::new(&format!(
+        r#"^\s*(pub(\([^)]*\))?\s+){}((const|async|unsafe|extern\s+"[^"]*")\s+)*(struct|enum|union|trait|type|fn|mod|static|const|macro)\s+{}\b|^\s*macro_rules!\s*{}\b"#,
+        if is_method { "?" } else { "" },
+        regex::escape(name),
+        regex::escape(name),
+    ))
+    .unwrap();
+
+    let mut candidates = files.to_vec();
+    rust_files(dir, &mut candidates)?;
+
+    for file in candidates {
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(_) => continue,
+        };
+
+        if let Some(line) = source.lines().position(|line| definition.is_match(line)) {
+            let docs = doctest::outer_docs(&source, line);
+            return Ok(Some((file, docs)));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Lists the rust files in `dir` and its subdirectories, in a consistent order. Tests are skipped,
+/// as they don't define public items.
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut entries: Vec<_> = dir.read_dir()?.flatten().map(|e| e.path()).collect();
+    entries.sort_unstable();
+
+    for path in entries {
+        let is_test = path
+            .file_stem()
+            .is_some_and(|stem| stem == "tests" || stem == "benches");
+
+        if is_test {
+            continue;
+        }
+
+        if path.is_dir() {
+            rust_files(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") && !files.contains(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -16,7 +16,13 @@ impl Gui {
 }
 
 impl OpenBackend for Gui {
-    fn run(&mut self, mut path: PathBuf, name: &str, opts: EditorOpts) -> error::Result<()> {
+    fn run(
+        &mut self,
+        path: PathBuf,
+        entry: &str,
+        name: &str,
+        opts: EditorOpts,
+    ) -> error::Result<()> {
         let self_path = env::current_exe()?;
         let mut watch_child = Command::new(self_path)
             .current_dir(&path)
@@ -27,16 +33,12 @@ impl OpenBackend for Gui {
         let mut editor = Command::new(opts.editor);
 
         editor.current_dir(&path);
-        path.clear(); // Now represents path to entrypoint (main.rs)
-
-        path.push("src");
-        path.push("main.rs");
 
         if !self.no_w {
             editor.arg("-w");
         }
 
-        editor.args(opts.args).arg(&path).output()?;
+        editor.args(opts.args).arg(entry).output()?;
 
         // Ignore error if user already killed it
         let _ = watch_child.kill();
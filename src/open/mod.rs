@@ -1,21 +1,32 @@
-use crate::{error, helpers};
+use crate::{error, helpers, kind::Kind, manifest::Manifest, metadata, metadata::Metadata};
 use std::path::{Path, PathBuf};
-use std::{env, io};
+use std::process::{self, Command, Stdio};
+use std::{env, fs, io};
 use structopt::StructOpt;
 
 mod gui;
 mod tmux;
 
 trait OpenBackend {
-    fn run(&mut self, path: PathBuf, name: &str, opts: crate::EditorOpts) -> error::Result<()>;
+    /// Opens the playground at `path`, with `entry` being the file to open relative to it.
+    fn run(
+        &mut self,
+        path: PathBuf,
+        entry: &str,
+        name: &str,
+        opts: crate::EditorOpts,
+    ) -> error::Result<()>;
 }
 
 #[derive(StructOpt, Debug)]
 pub struct OpenOpts {
     #[structopt(flatten)]
     pub(crate) editor_opts: super::EditorOpts,
-    /// The name of the playground to open
-    pub(crate) name: String,
+    /// The name of the playground to open. If not supplied, it is picked from a list
+    pub(crate) name: Option<String>,
+    /// Only pick from the playgrounds with this tag. Can be given multiple times
+    #[structopt(long = "tag", number_of_values = 1, conflicts_with = "name")]
+    pub(crate) tags: Vec<String>,
     /// Do not pass -w flag when opening GUI editor
     #[structopt(long, requires("gui"))]
     pub(crate) no_w: bool,
@@ -27,8 +38,16 @@ pub struct OpenOpts {
 }
 
 pub fn open(opts: OpenOpts) -> error::Result<()> {
+    let name = match opts.name {
+        Some(name) => name,
+        None => match helpers::pick_playground("Pick a playground to open:", &opts.tags)? {
+            Some(name) => name,
+            None => return Ok(()),
+        },
+    };
+
     let mut path = helpers::get_dir();
-    path.push(&opts.name); // Now represents playground path
+    path.push(&name); // Now represents playground path
 
     if !opts.skip_check && !path.is_dir() {
         return Err(error::Error::new(
@@ -41,18 +60,84 @@ pub fn open(opts: OpenOpts) -> error::Result<()> {
         ));
     }
 
-    if opts.gui {
-        helpers::print_status("Opening", &opts.name);
-        gui::Gui::new(opts.no_w).run(path, &opts.name, opts.editor_opts)
+    let entry = Kind::of(&Manifest::open(path.join("Cargo.toml"))?)?.entry();
+
+    let mut backend: Box<dyn OpenBackend> = if opts.gui {
+        Box::new(gui::Gui::new(opts.no_w))
     } else if env::var_os("TMUX").is_some() {
-        helpers::print_status("Opening", &opts.name);
-        tmux::Tmux.run(path, &opts.name, opts.editor_opts)
+        Box::new(tmux::Tmux)
     } else {
-        Err(error::Error::new(
+        return Err(error::Error::new(
             io::ErrorKind::Other,
             "currently only terminals running tmux are supported",
         )
-        .with_help("try using the --gui flag with a GUI editor"))
+        .with_help("try using the --gui flag with a GUI editor"));
+    };
+
+    // the playground only counts as opened once there is a backend to open it with
+    let _lock = OpenLock::create(&path);
+
+    // failing to record when it was opened shouldn't keep the playground from opening
+    let _ = Metadata::update(&path, |metadata| {
+        metadata.last_opened = Some(metadata::now())
+    });
+
+    helpers::print_status("Opening", &name);
+    backend.run(path, entry, &name, opts.editor_opts)
+}
+
+/// Where the lock marking a playground as open is, relative to the playground. It is outside the
+/// target directory, so `cargo clean` doesn't remove it, and is ignored by the watcher.
+pub const OPEN_LOCK: &str = ".playground.lock";
+
+/// Marks a playground as open while it exists, so it isn't moved from under the editor.
+struct OpenLock(PathBuf);
+
+impl OpenLock {
+    /// Creates the lock. Failing to create it isn't an error, as it only guards other commands.
+    fn create(playground: &Path) -> Option<Self> {
+        let path = playground.join(OPEN_LOCK);
+        fs::write(&path, process::id().to_string()).ok()?;
+
+        Some(OpenLock(path))
+    }
+}
+
+impl Drop for OpenLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Checks whether the playground is open in an editor, returning the path to its lock if it is. A
+/// lock left behind by an `open` which was killed is stale, and is removed.
+pub fn lock_of(playground: &Path) -> Option<PathBuf> {
+    let path = playground.join(OPEN_LOCK);
+    let pid = fs::read_to_string(&path).ok()?;
+
+    if !is_running(pid.trim()) {
+        let _ = fs::remove_file(&path);
+        return None;
+    }
+
+    Some(path)
+}
+
+/// Checks whether the process with the id `pid` is running. Where this can't be checked, it is
+/// assumed to be.
+fn is_running(pid: &str) -> bool {
+    if pid.parse::<u32>().is_err() {
+        return false;
+    }
+
+    if cfg!(unix) {
+        Command::new("kill")
+            .args(["-0", pid])
+            .stderr(Stdio::null())
+            .status()
+            .map_or(true, |status| status.success())
+    } else {
+        true
     }
 }
 
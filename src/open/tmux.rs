@@ -8,7 +8,13 @@ use std::process::Command;
 pub struct Tmux;
 
 impl OpenBackend for Tmux {
-    fn run(&mut self, mut path: PathBuf, name: &str, opts: EditorOpts) -> error::Result<()> {
+    fn run(
+        &mut self,
+        path: PathBuf,
+        entry: &str,
+        name: &str,
+        opts: EditorOpts,
+    ) -> error::Result<()> {
         let self_path = env::current_exe()?;
         let watch_cmd = format!(
             "{} watch {}",
@@ -18,7 +24,7 @@ impl OpenBackend for Tmux {
 
         #[rustfmt::skip]
         Command::new("tmux")
-            .args(&[
+            .args([
                 "split-window", "-h",
                 "-e", "HISTFILE=/dev/null",              // prevent command from going into history
                 "-c", path_to_str(&path, "playground")?,
@@ -30,15 +36,10 @@ impl OpenBackend for Tmux {
         let mut editor = Command::new(opts.editor);
 
         editor.current_dir(&path);
-        path.clear(); // Now represents path to entrypoint (main.rs)
-
-        path.push("src");
-        path.push("main.rs");
-
-        editor.args(opts.args).arg(&path).status()?;
+        editor.args(opts.args).arg(entry).status()?;
 
         #[rustfmt::skip]
-        Command::new("tmux").args(&[
+        Command::new("tmux").args([
             "select-pane", "-R", ";", // Select the right pane
             "send-keys", "C-c",       // and kill it
         ]).output()?;
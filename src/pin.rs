@@ -0,0 +1,22 @@
+use crate::{error, helpers, metadata::Metadata};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct PinOpts {
+    /// The name of the playground
+    playground: String,
+}
+
+/// Pins or unpins a playground. Pinned playgrounds are kept by `clean`, unless it is given
+/// `--include-pinned`.
+pub fn pin(opts: PinOpts, pinned: bool) -> error::Result<()> {
+    let playground = helpers::playground_path(&opts.playground)?;
+
+    Metadata::update(&playground, |metadata| metadata.pinned = pinned)?;
+
+    let status = if pinned { "Pinned" } else { "Unpinned" };
+    helpers::print_status(status, &opts.playground);
+    println!();
+
+    Ok(())
+}
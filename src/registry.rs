@@ -0,0 +1,283 @@
+use crate::{dep::Dep, error, helpers};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{atomic, Arc};
+
+/// The maximum number of crates to list when a crate isn't available offline.
+const MAX_LISTED: usize = 20;
+
+/// Parts of the errors cargo gives when the network or the registry can't be reached. Any other
+/// error, such as a crate which doesn't exist, wouldn't be fixed by using the local registry cache.
+const NETWORK_ERRORS: &[&str] = &[
+    "failed to download from",
+    "could not resolve host",
+    "couldn't resolve host",
+    "could not connect",
+    "failed to connect",
+    "connection refused",
+    "connection reset",
+    "timed out",
+    "network",
+];
+
+/// How the dependencies of a playground were fetched.
+enum Fetched {
+    Online,
+    /// From the local registry cache. `fell_back` is set if the network was tried first
+    Offline {
+        fell_back: bool,
+    },
+    /// The dependencies couldn't be resolved. Holds the error from cargo if the network could be
+    /// reached, as then it is the reason
+    Failed(Option<String>),
+}
+
+/// Lists the crates in the local registry cache (`~/.cargo/registry/cache`), along with the
+/// versions of each that are available.
+pub fn cached_crates() -> io::Result<BTreeMap<String, Vec<String>>> {
+    let mut cache = home::cargo_home()?;
+    cache.push("registry");
+    cache.push("cache");
+
+    let mut crates = BTreeMap::<_, Vec<_>>::new();
+
+    if !cache.is_dir() {
+        return Ok(crates);
+    }
+
+    // The crate name can contain `-`, so the version is the first part which looks like one
+    let file_name = regex::Regex::new(r"^(.+?)-(\d+\.\d+\.\d+.*)\.crate$").unwrap();
+
+    for registry in cache.read_dir()?.flatten() {
+        for file in registry.path().read_dir().into_iter().flatten().flatten() {
+            let file = file.file_name();
+            let captures = match file.to_str().and_then(|file| file_name.captures(file)) {
+                Some(captures) => captures,
+                None => continue,
+            };
+
+            let versions = crates.entry(captures[1].to_owned()).or_default();
+            if !versions.iter().any(|v| v == &captures[2]) {
+                versions.push(captures[2].to_owned());
+            }
+        }
+    }
+
+    for versions in crates.values_mut() {
+        versions.sort_by_cached_key(|version| version_key(version));
+    }
+
+    Ok(crates)
+}
+
+/// A key to sort versions by, which compares the major, minor and patch numbers numerically.
+fn version_key(version: &str) -> (Vec<u64>, String) {
+    let (numbers, rest) = match version.find(&['-', '+'][..]) {
+        Some(i) => version.split_at(i),
+        None => (version, ""),
+    };
+
+    let numbers = numbers.split('.').map(|n| n.parse().unwrap_or(0)).collect();
+
+    (numbers, rest.to_owned())
+}
+
+/// Fetches the dependencies of the playground with the given manifest. Unless `offline` is set,
+/// they are first fetched from the network, falling back to the local registry cache if it can't
+/// be reached. Returns whether the dependencies were resolved offline.
+///
+/// `deps` are the dependencies that were requested by the user, which are used to explain what is
+/// missing if they cannot be resolved offline.
+pub fn fetch(manifest: &Path, deps: &[&Dep], offline: bool) -> error::Result<bool> {
+    if offline {
+        // Avoid running cargo at all if it is known to fail
+        check_cached(deps)?;
+    }
+
+    let stop = Arc::new(false.into());
+    let loader = helpers::loader("fetching dependencies", Arc::clone(&stop));
+
+    let res = fetch_inner(manifest, offline);
+
+    // only one writer, and reader doesn't care about the race, so its fine
+    stop.store(true, atomic::Ordering::Relaxed);
+    let _ = loader.join();
+
+    match res? {
+        Fetched::Online => Ok(false),
+        Fetched::Offline { fell_back } => {
+            if fell_back {
+                helpers::print_status(
+                    "Offline",
+                    "couldn't reach the network, used the local registry cache",
+                );
+                println!();
+            }
+
+            Ok(true)
+        }
+        Fetched::Failed(Some(stderr)) => Err(error::Error::new(
+            io::ErrorKind::Other,
+            format!("could not fetch the dependencies: {}", cargo_error(&stderr)),
+        )
+        .with_help(
+            "check that the dependencies exist, and that the requested versions are published",
+        )),
+        Fetched::Failed(None) => {
+            check_cached(deps)?;
+
+            Err(error::Error::new(
+                io::ErrorKind::Other,
+                "could not resolve the dependencies from the network or the local registry cache",
+            )
+            .with_help(versions_help(deps)))
+        }
+    }
+}
+
+fn fetch_inner(manifest: &Path, offline: bool) -> io::Result<Fetched> {
+    if !offline {
+        match cargo_fetch(manifest, false)? {
+            Ok(()) => return Ok(Fetched::Online),
+            Err(stderr) if !is_network_error(&stderr) => return Ok(Fetched::Failed(Some(stderr))),
+            Err(_) => {}
+        }
+    }
+
+    match cargo_fetch(manifest, true)? {
+        Ok(()) => Ok(Fetched::Offline {
+            fell_back: !offline,
+        }),
+        Err(_) => Ok(Fetched::Failed(None)),
+    }
+}
+
+/// Runs `cargo fetch` for the given manifest, returning what cargo printed to stderr if it failed.
+fn cargo_fetch(manifest: &Path, offline: bool) -> io::Result<Result<(), String>> {
+    let mut cmd = Command::new("cargo");
+    cmd.arg("fetch").arg("--manifest-path").arg(manifest);
+
+    if offline {
+        cmd.arg("--offline");
+    }
+
+    let output = cmd.stdout(Stdio::null()).stderr(Stdio::piped()).output()?;
+
+    if output.status.success() {
+        Ok(Ok(()))
+    } else {
+        Ok(Err(String::from_utf8_lossy(&output.stderr).into_owned()))
+    }
+}
+
+fn is_network_error(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    NETWORK_ERRORS.iter().any(|error| stderr.contains(error))
+}
+
+/// The error cargo printed, without the statuses before it.
+fn cargo_error(stderr: &str) -> &str {
+    stderr
+        .find("error: ")
+        .map_or(stderr, |start| &stderr[start + "error: ".len()..])
+        .trim_end()
+}
+
+/// Makes sure all the registry dependencies are present in the local registry cache.
+fn check_cached(deps: &[&Dep]) -> error::Result<()> {
+    let cached = cached_crates()?;
+
+    let missing: Vec<_> = deps
+        .iter()
+        .filter(|dep| dep.git.is_none() && dep.path.is_none())
+        .map(|dep| dep.package_name())
+        .filter(|name| !cached.contains_key(*name))
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(error::Error::new(
+        io::ErrorKind::NotFound,
+        format!(
+            "crates not available in the local registry cache: {}",
+            missing.join(", ")
+        ),
+    )
+    .with_help(cached_help(&missing, &cached)))
+}
+
+/// Lists the cached crates, preferring those with names similar to the missing ones.
+fn cached_help(missing: &[&str], cached: &BTreeMap<String, Vec<String>>) -> String {
+    if cached.is_empty() {
+        return "the local registry cache is empty, connect to the internet to fetch crates".into();
+    }
+
+    let normalise = |name: &str| name.to_lowercase().replace('-', "_");
+    let missing: Vec<_> = missing.iter().map(|name| normalise(name)).collect();
+
+    let similar: Vec<_> = cached
+        .keys()
+        .filter(|name| {
+            let name = normalise(name);
+            missing
+                .iter()
+                .any(|missing| name.contains(missing.as_str()) || missing.contains(&name))
+        })
+        .collect();
+
+    let (prefix, listed) = if similar.is_empty() {
+        (
+            "crates in the local registry cache",
+            cached.keys().collect(),
+        )
+    } else {
+        ("similar crates in the local registry cache", similar)
+    };
+
+    let mut help = format!(
+        "{}: {}",
+        prefix,
+        listed
+            .iter()
+            .take(MAX_LISTED)
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    if listed.len() > MAX_LISTED {
+        help += &format!(" and {} more", listed.len() - MAX_LISTED);
+    }
+
+    help
+}
+
+/// Lists the cached versions of the registry dependencies.
+fn versions_help(deps: &[&Dep]) -> String {
+    let cached = cached_crates().unwrap_or_default();
+
+    let versions: Vec<_> = deps
+        .iter()
+        .filter_map(|dep| {
+            let versions = cached.get(dep.package_name())?;
+            // the newest versions are the most likely to be relevant
+            let newest: Vec<_> = versions.iter().rev().take(MAX_LISTED).cloned().collect();
+            Some(format!("{} {}", dep.package_name(), newest.join(", ")))
+        })
+        .collect();
+
+    if versions.is_empty() {
+        return "check that the dependencies exist, and that you are connected to the internet"
+            .into();
+    }
+
+    format!(
+        "check that the requested versions are available offline
+       the cached versions are: {}",
+        versions.join("; ")
+    )
+}
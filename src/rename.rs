@@ -0,0 +1,60 @@
+use crate::{error, helpers, manifest::Manifest, new::names, open};
+use std::path::Path;
+use std::{fs, io};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct MvOpts {
+    /// The name of the playground to rename
+    old: String,
+    /// The new name of the playground
+    new: String,
+}
+
+/// Renames a playground, both its directory and its package.
+pub fn mv(opts: MvOpts) -> error::Result<()> {
+    let dir = helpers::get_dir();
+    let old = helpers::playground_path(&opts.old)?;
+    let new = dir.join(&opts.new);
+
+    names::validate_name(&opts.new)?;
+
+    if new.exists() {
+        return Err(error::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("playground '{}' already exists", opts.new),
+        )
+        .with_help("choose a different name, or rename that playground first"));
+    }
+
+    // The editor and watcher of an open playground would keep using the old directory
+    if let Some(lock) = open::lock_of(&old) {
+        return Err(error::Error::new(
+            io::ErrorKind::Other,
+            format!("playground '{}' is open", opts.old),
+        )
+        .with_help(format!(
+            "close its editor first. If it isn't open, remove {:?}",
+            lock
+        )));
+    }
+
+    fs::rename(&old, &new)?;
+
+    // The directory is moved back if the package can't be renamed, so the two don't get out of sync
+    if let Err(err) = rename_package(&new, &opts.new) {
+        fs::rename(&new, &old)?;
+        return Err(err);
+    }
+
+    helpers::print_status("Renamed", &format!("{} to {}", opts.old, opts.new));
+    println!();
+
+    Ok(())
+}
+
+fn rename_package(playground: &Path, name: &str) -> error::Result<()> {
+    let mut manifest = Manifest::open(playground.join("Cargo.toml"))?;
+    manifest.set_package_field("name", name)?;
+    manifest.save()
+}
@@ -0,0 +1,144 @@
+use crate::{error, kind::Kind, manifest::Manifest, metadata, metadata::Metadata};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::Path;
+use std::process::{self, Command, Stdio};
+use std::{fs, thread};
+
+/// Where the last run of a playground is recorded, relative to the playground. It is in the target
+/// directory so the watcher ignores it, and so it is removed along with the build artifacts.
+const LAST_RUN: &str = "target/cargo-playground/last-run.json";
+/// The maximum number of bytes of each output to record, so runaway output doesn't fill the disk.
+const MAX_RECORDED: usize = 64 * 1024;
+
+/// The output of the most recent run of a playground by the watcher.
+pub struct LastRun {
+    pub command: String,
+    pub stdout: String,
+    pub stderr: String,
+    /// The exit status, which is `None` if the run was killed by a signal
+    pub status: Option<i32>,
+    /// Whether the output was too long to be recorded in full
+    pub truncated: bool,
+}
+
+impl LastRun {
+    /// Reads the last run of the playground, if it has been run since it was last cleaned.
+    pub fn read(playground: &Path) -> Option<Self> {
+        let contents = fs::read(playground.join(LAST_RUN)).ok()?;
+        let run: Value = serde_json::from_slice(&contents).ok()?;
+
+        Some(LastRun {
+            command: run["command"].as_str()?.to_owned(),
+            stdout: run["stdout"].as_str()?.to_owned(),
+            stderr: run["stderr"].as_str()?.to_owned(),
+            status: run["status"].as_i64().map(|status| status as i32),
+            truncated: run["truncated"].as_bool().unwrap_or(false),
+        })
+    }
+
+    fn write(&self, playground: &Path) -> io::Result<()> {
+        let path = playground.join(LAST_RUN);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let run = json!({
+            "command": self.command,
+            "stdout": self.stdout,
+            "stderr": self.stderr,
+            "status": self.status,
+            "truncated": self.truncated,
+        });
+
+        // The file may be a hard link shared with a clone of the playground, so it is replaced
+        // rather than written to
+        let _ = fs::remove_file(&path);
+        fs::write(path, run.to_string())
+    }
+}
+
+/// Runs the playground in the current directory like the watcher would, passing its output through
+/// while recording it for `share`. Exits with the exit status of the run.
+pub fn run() -> error::Result<()> {
+    let command = Kind::of(&Manifest::open("Cargo.toml")?)?.command();
+    let mut parts = command.split_whitespace();
+
+    let mut cmd = Command::new(parts.next().unwrap_or_default());
+    cmd.args(parts);
+
+    // cargo can't tell that its output still ends up in a terminal
+    if io::stderr().is_terminal() {
+        cmd.env("CARGO_TERM_COLOR", "always");
+    }
+
+    let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+    let stdout = child.stdout.take().map(|from| tee(from, io::stdout()));
+    let stderr = child.stderr.take().map(|from| tee(from, io::stderr()));
+
+    let status = child.wait()?;
+
+    let recorded = |handle: Option<thread::JoinHandle<(Vec<u8>, bool)>>| {
+        handle
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default()
+    };
+    let (stdout, stdout_truncated) = recorded(stdout);
+    let (stderr, stderr_truncated) = recorded(stderr);
+
+    let run = LastRun {
+        command: command.to_owned(),
+        stdout: strip_ansi(&stdout),
+        stderr: strip_ansi(&stderr),
+        status: status.code(),
+        truncated: stdout_truncated || stderr_truncated,
+    };
+
+    // failing to record the run shouldn't affect the run itself
+    let _ = run.write(Path::new("."));
+    let _ = Metadata::update(Path::new("."), |metadata| {
+        metadata.last_run = Some(metadata::now())
+    });
+
+    process::exit(status.code().unwrap_or(1));
+}
+
+/// Copies everything from `from` to `to` on a new thread, recording up to `MAX_RECORDED` bytes of
+/// it. The thread returns the recorded bytes, and whether any were left out.
+fn tee<R, W>(mut from: R, mut to: W) -> thread::JoinHandle<(Vec<u8>, bool)>
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut recorded = Vec::new();
+        let mut truncated = false;
+        let mut buf = [0; 4096];
+
+        loop {
+            let read = match from.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(read) => read,
+            };
+
+            let _ = to.write_all(&buf[..read]);
+            let _ = to.flush();
+
+            let space = MAX_RECORDED - recorded.len();
+            truncated |= read > space;
+            recorded.extend_from_slice(&buf[..read.min(space)]);
+        }
+
+        (recorded, truncated)
+    })
+}
+
+/// Removes the escape codes used for colours and cursor movement.
+fn strip_ansi(output: &[u8]) -> String {
+    let escape = Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").unwrap();
+    escape
+        .replace_all(&String::from_utf8_lossy(output), "")
+        .into_owned()
+}
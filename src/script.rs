@@ -0,0 +1,213 @@
+/// A single file package in the cargo-script format, ie rust code with the manifest embedded in it.
+pub struct Script {
+    /// The embedded manifest, if any
+    pub manifest: Option<String>,
+    /// The code, without the embedded manifest
+    pub code: String,
+}
+
+impl Script {
+    /// Splits a cargo script into its code and embedded manifest. The manifest can either be in a
+    /// frontmatter block, or in a `cargo` code block in the doc comment at the start of the file
+    /// (the format used by the older cargo-script tools).
+    pub fn parse(source: &str) -> Self {
+        if let Some(script) = parse_frontmatter(source) {
+            return script;
+        }
+
+        Script {
+            manifest: doc_manifest(source),
+            code: source.to_owned(),
+        }
+    }
+
+    /// Renders the script in the cargo-script format, with the manifest in a frontmatter block.
+    pub fn render(&self) -> String {
+        let manifest = match self.manifest {
+            Some(ref manifest) => manifest.trim(),
+            None => return self.code.clone(),
+        };
+
+        // The fence has to be longer than any line of dashes in the manifest
+        let longest = manifest
+            .lines()
+            .map(str::trim_end)
+            .filter(|line| !line.is_empty() && line.chars().all(|c| c == '-'))
+            .map(str::len)
+            .max()
+            .unwrap_or(0);
+        let fence = "-".repeat(longest.max(2) + 1);
+
+        format!("{}\n{}\n{}\n\n{}", fence, manifest, fence, self.code)
+    }
+}
+
+/// Parses a script with a frontmatter block, such as
+///
+/// ```text
+/// #!/usr/bin/env -S cargo +nightly -Zscript
+/// ---
+/// [dependencies]
+/// rand = "0.8"
+/// ---
+///
+/// fn main() {}
+/// ```
+///
+/// The shebang line is not part of the code, as it is only meaningful for scripts.
+fn parse_frontmatter(source: &str) -> Option<Script> {
+    let mut rest = source;
+
+    // `#![` is an inner attribute rather than a shebang
+    if rest.starts_with("#!") && !rest.starts_with("#![") {
+        rest = rest.split_once('\n').map_or("", |(_, rest)| rest);
+    }
+
+    let (opening, body) = rest.trim_start().split_once('\n')?;
+    let opening = opening.trim_end();
+
+    let dashes = opening.chars().take_while(|&c| c == '-').count();
+    let info = opening[dashes..].trim();
+
+    if dashes < 3 || !(info.is_empty() || info == "cargo") {
+        return None;
+    }
+
+    let fence = &opening[..dashes];
+    let mut manifest = String::new();
+    let mut end = 0;
+
+    for line in body.split_inclusive('\n') {
+        end += line.len();
+
+        if line.trim_end() == fence {
+            return Some(Script {
+                manifest: Some(manifest),
+                code: body[end..].trim_start_matches(['\r', '\n']).to_owned(),
+            });
+        }
+
+        manifest.push_str(line);
+    }
+
+    None
+}
+
+/// Finds a manifest in a `cargo` code block in the doc comment at the start of a script, such as
+///
+/// ```text
+/// //! ```cargo
+/// //! [dependencies]
+/// //! rand = "0.8"
+/// //! ```
+/// ```
+fn doc_manifest(source: &str) -> Option<String> {
+    let mut manifest: Option<String> = None;
+
+    for line in source.lines() {
+        let trimmed = line.trim_start();
+
+        let doc = match trimmed.strip_prefix("//!") {
+            Some(doc) => doc.strip_prefix(' ').unwrap_or(doc),
+            None if trimmed.is_empty() || trimmed.starts_with("#!") => continue,
+            // the doc comment has ended
+            None => break,
+        };
+
+        match manifest {
+            Some(_) if doc.trim() == "```" => return manifest,
+            Some(ref mut manifest) => {
+                manifest.push_str(doc);
+                manifest.push('\n');
+            }
+            None if doc.trim() == "```cargo" => manifest = Some(String::new()),
+            None => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_frontmatter() {
+        let script = Script::parse("---\n[dependencies]\nrand = \"0.8\"\n---\n\nfn main() {}\n");
+        assert_eq!(
+            script.manifest.as_deref(),
+            Some("[dependencies]\nrand = \"0.8\"\n")
+        );
+        assert_eq!(script.code, "fn main() {}\n");
+    }
+
+    #[test]
+    fn skips_the_shebang() {
+        let script = Script::parse(
+            "#!/usr/bin/env -S cargo +nightly -Zscript\n---\n[package]\n---\nfn main() {}\n",
+        );
+        assert_eq!(script.manifest.as_deref(), Some("[package]\n"));
+        assert_eq!(script.code, "fn main() {}\n");
+
+        // an inner attribute isn't a shebang
+        let source = "#![allow(unused)]\nfn main() {}\n";
+        let script = Script::parse(source);
+        assert_eq!(script.manifest, None);
+        assert_eq!(script.code, source);
+    }
+
+    #[test]
+    fn parses_longer_fences_and_the_cargo_info_string() {
+        let script = Script::parse("----cargo\n[package]\n---\n----\nfn main() {}\n");
+        assert_eq!(script.manifest.as_deref(), Some("[package]\n---\n"));
+        assert_eq!(script.code, "fn main() {}\n");
+
+        // other info strings aren't cargo manifests
+        let script = Script::parse("---toml\n[package]\n---\nfn main() {}\n");
+        assert_eq!(script.manifest, None);
+    }
+
+    #[test]
+    fn parses_doc_manifests() {
+        let source = r#"//! A script
+//!
+//! ```cargo
+//! [dependencies]
+//! rand = "0.8"
+//! ```
+
+fn main() {}
+"#;
+        let script = Script::parse(source);
+        assert_eq!(
+            script.manifest.as_deref(),
+            Some("[dependencies]\nrand = \"0.8\"\n")
+        );
+        assert_eq!(script.code, source);
+    }
+
+    #[test]
+    fn round_trips() {
+        let script = Script {
+            manifest: Some("[package]\n---\n[dependencies]\nrand = \"0.8\"\n".into()),
+            code: "fn main() {}\n".into(),
+        };
+
+        let rendered = script.render();
+        assert!(rendered.starts_with("----\n"));
+
+        let parsed = Script::parse(&rendered);
+        assert_eq!(
+            parsed.manifest.as_deref().map(str::trim),
+            script.manifest.as_deref().map(str::trim)
+        );
+        assert_eq!(parsed.code, script.code);
+
+        let script = Script {
+            manifest: None,
+            code: "fn main() {}\n".into(),
+        };
+        assert_eq!(script.render(), script.code);
+    }
+}
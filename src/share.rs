@@ -0,0 +1,183 @@
+use crate::{error, helpers, kind::Kind, manifest::Manifest, run::LastRun};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{fs, io};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+pub struct ShareOpts {
+    /// The name of the playground to share
+    playground: String,
+    /// Print the playground as Markdown
+    #[structopt(long)]
+    markdown: bool,
+}
+
+/// Prints a playground in a form which can be pasted into bug reports and code reviews.
+pub fn share(opts: ShareOpts) -> error::Result<()> {
+    let playground = helpers::playground_path(&opts.playground)?;
+
+    // Other formats may be added later, so the format has to be chosen explicitly
+    if !opts.markdown {
+        return Err(error::Error::new(
+            io::ErrorKind::InvalidInput,
+            "no format to share the playground in was given",
+        )
+        .with_help("use --markdown, which is currently the only format"));
+    }
+
+    print!("{}", markdown(&opts.playground, &playground)?);
+
+    Ok(())
+}
+
+/// Renders the dependencies, source files, toolchain version and last run of a playground as
+/// Markdown.
+fn markdown(name: &str, playground: &Path) -> error::Result<String> {
+    let manifest = Manifest::open(playground.join("Cargo.toml"))?;
+    let kind = Kind::of(&manifest)?;
+
+    let mut md = format!("### Playground `{}`\n\n", name);
+
+    let toolchain = rustc_version(playground).unwrap_or_else(|| "unknown".into());
+    let _ = write!(md, "**Toolchain:** `{}`", toolchain);
+    if let Some(edition) = manifest.package_field("edition") {
+        let _ = write!(md, ", edition {}", edition);
+    }
+    md.push_str("\n\n**Dependencies:**");
+
+    let deps = manifest.dependencies();
+    if deps.is_empty() {
+        md.push_str(" none\n\n");
+    } else {
+        md.push('\n');
+        md.push_str(&fenced(
+            "toml",
+            &deps
+                .iter()
+                .map(|dep| format!("{}\n", dep))
+                .collect::<String>(),
+        ));
+        md.push('\n');
+    }
+
+    let mut files = Vec::new();
+    source_files(&playground.join("src"), &mut files)?;
+
+    // The entry of bench playgrounds is outside `src`
+    let entry = playground.join(kind.entry());
+    if !files.contains(&entry) {
+        files.push(entry);
+    }
+
+    for file in files {
+        let contents = match fs::read_to_string(&file) {
+            Ok(contents) => contents,
+            // binary files can't be shared as text
+            Err(_) => continue,
+        };
+
+        let relative = file.strip_prefix(playground).unwrap_or(&file);
+        let lang = match file.extension().and_then(|ext| ext.to_str()) {
+            Some("rs") => "rust",
+            Some(ext) => ext,
+            None => "",
+        };
+
+        let _ = writeln!(md, "**`{}`**\n", relative.display());
+        md.push_str(&fenced(lang, &contents));
+        md.push('\n');
+    }
+
+    let run = match LastRun::read(playground) {
+        Some(run) => run,
+        None => {
+            md.push_str("*The playground hasn't been run yet.*\n");
+            return Ok(md);
+        }
+    };
+
+    match run.status {
+        Some(status) => {
+            let _ = write!(
+                md,
+                "**Last run:** `{}` exited with status {}",
+                run.command, status
+            );
+        }
+        None => {
+            let _ = write!(md, "**Last run:** `{}` was killed by a signal", run.command);
+        }
+    }
+    if run.truncated {
+        md.push_str(" (output truncated)");
+    }
+    md.push_str("\n\n");
+
+    for (label, output) in [("stdout", &run.stdout), ("stderr", &run.stderr)] {
+        if !output.is_empty() {
+            let _ = writeln!(md, "{}:\n", label);
+            md.push_str(&fenced("text", output));
+            md.push('\n');
+        }
+    }
+
+    Ok(md)
+}
+
+/// Wraps `contents` in a fenced code block, using a fence which is longer than any run of
+/// backticks in it.
+fn fenced(lang: &str, contents: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+
+    for c in contents.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+
+    let fence = "`".repeat(longest.max(2) + 1);
+    let newline = if contents.ends_with('\n') { "" } else { "\n" };
+
+    format!("{}{}\n{}{}{}\n", fence, lang, contents, newline, fence)
+}
+
+/// Gets the version of the compiler used by the playground, which depends on its toolchain.
+fn rustc_version(playground: &Path) -> Option<String> {
+    let output = Command::new("rustc")
+        .arg("--version")
+        .current_dir(playground)
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// Lists the files in `dir` and its subdirectories, sorted by their path.
+fn source_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    let mut entries: Vec<_> = dir.read_dir()?.flatten().map(|e| e.path()).collect();
+    entries.sort_unstable();
+
+    for path in entries {
+        if path.is_dir() {
+            source_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use crate::{error, helpers, metadata::Metadata};
+use crossterm::style::Stylize;
+use std::io;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+// Otherwise tags to remove would be taken for flags
+#[structopt(setting = structopt::clap::AppSettings::AllowLeadingHyphen)]
+pub struct TagOpts {
+    /// The name of the playground to tag
+    playground: String,
+    /// The tags to add (`+foo`, or just `foo`) and remove (`-foo`). If none are given, the tags of
+    /// the playground are printed
+    changes: Vec<String>,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct DescribeOpts {
+    /// The name of the playground to describe
+    playground: String,
+    /// The description of the playground. An empty description removes it, and if none is given
+    /// the current description is printed
+    description: Option<String>,
+}
+
+/// Adds and removes the tags of a playground.
+pub fn tag(opts: TagOpts) -> error::Result<()> {
+    let playground = helpers::playground_path(&opts.playground)?;
+
+    let mut metadata = Metadata::load(&playground);
+
+    if opts.changes.is_empty() {
+        for tag in &metadata.tags {
+            println!("{}", tag);
+        }
+
+        return Ok(());
+    }
+
+    for change in &opts.changes {
+        match change.strip_prefix('-') {
+            Some(tag) => metadata.tags.retain(|existing| existing != tag),
+            None => {
+                let tag = change.strip_prefix('+').unwrap_or(change);
+                validate_tag(tag)?;
+
+                if !metadata.tags.iter().any(|existing| existing == tag) {
+                    metadata.tags.push(tag.to_owned());
+                }
+            }
+        }
+    }
+
+    metadata.save(&playground)?;
+
+    let tags = if metadata.tags.is_empty() {
+        "no tags".to_owned()
+    } else {
+        metadata.tags.join(", ")
+    };
+    helpers::print_status(
+        "Tagged",
+        &format!("{} {}", opts.playground, tags.dark_grey()),
+    );
+    println!();
+
+    Ok(())
+}
+
+/// Sets or prints the description of a playground.
+pub fn describe(opts: DescribeOpts) -> error::Result<()> {
+    let playground = helpers::playground_path(&opts.playground)?;
+
+    let description = match opts.description {
+        Some(description) => description,
+        None => {
+            if let Some(description) = Metadata::load(&playground).description {
+                println!("{}", description);
+            }
+
+            return Ok(());
+        }
+    };
+
+    let description = Some(description.trim().to_owned()).filter(|desc| !desc.is_empty());
+    Metadata::update(&playground, |metadata| metadata.description = description)?;
+
+    helpers::print_status("Described", &opts.playground);
+    println!();
+
+    Ok(())
+}
+
+/// Checks that `tag` can be used as a tag. Tags are single words, so they can be given on the
+/// command line and listed after the name of a playground.
+pub fn validate_tag(tag: &str) -> error::Result<()> {
+    if tag.is_empty()
+        || tag.starts_with(['+', '-'])
+        || tag.contains(|c: char| c.is_whitespace() || c == ',')
+    {
+        return Err(error::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid tag '{}'", tag),
+        )
+        .with_help("tags can't be empty, start with `+` or `-`, or contain whitespace or `,`"));
+    }
+
+    Ok(())
+}
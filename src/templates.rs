@@ -0,0 +1,137 @@
+use crate::{error, helpers, manifest::Manifest, metadata::METADATA_FILE, open::OPEN_LOCK};
+use std::path::Path;
+use std::{fs, io};
+use structopt::StructOpt;
+
+/// The manifest fragment in a template, which is merged into the playground's Cargo.toml.
+const FRAGMENT: &str = "Cargo.toml";
+/// Files and directories in a playground which are not part of a template.
+const IGNORED: &[&str] = &["target", "Cargo.lock", ".git", METADATA_FILE, OPEN_LOCK];
+
+#[derive(StructOpt, Debug)]
+pub struct SaveTemplateOpts {
+    /// The name of the playground to save as a template
+    playground: String,
+    /// The name of the template
+    template: String,
+    /// Overwrite the template if it already exists
+    #[structopt(short, long)]
+    force: bool,
+}
+
+/// Saves an existing playground as a named template.
+pub fn save(opts: SaveTemplateOpts) -> error::Result<()> {
+    let playground = helpers::playground_path(&opts.playground)?;
+
+    validate_name(&opts.template)?;
+
+    let template = helpers::get_templates_dir().join(&opts.template);
+
+    if template.exists() {
+        if !opts.force {
+            return Err(error::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("template '{}' already exists", opts.template),
+            )
+            .with_help("use --force to overwrite it, or choose a different name"));
+        }
+
+        fs::remove_dir_all(&template)?;
+    }
+
+    helpers::copy_dir(&playground, &template, IGNORED, None)?;
+
+    // The package name is given by the playground it is used in, so it shouldn't be saved
+    let mut manifest = Manifest::open(template.join(FRAGMENT))?;
+    manifest.remove_package_field("name")?;
+    manifest.save()?;
+
+    helpers::print_status(
+        "Saved",
+        &format!("{} as {}", opts.playground, opts.template),
+    );
+    println!();
+
+    Ok(())
+}
+
+/// Applies a named template to a freshly created playground. The files in the template are copied
+/// to it (replacing its `src` directory, if the template has one), and the manifest fragment is
+/// merged into its Cargo.toml.
+///
+/// Occurrences of `{{name}}` and `{{crate_name}}` are replaced by the name of the playground.
+pub fn apply(
+    template: &str,
+    playground: &Path,
+    name: &str,
+    manifest: &mut Manifest,
+) -> error::Result<()> {
+    validate_name(template)?;
+
+    let templates_dir = helpers::get_templates_dir();
+    let template_dir = templates_dir.join(template);
+
+    if !template_dir.is_dir() {
+        let mut available: Vec<_> = templates_dir
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        available.sort_unstable();
+
+        let help = if available.is_empty() {
+            format!(
+                "there are no templates in {:?}
+       use `cargo playground save-template` to save a playground as a template",
+                templates_dir
+            )
+        } else {
+            format!("the available templates are: {}", available.join(", "))
+        };
+
+        return Err(error::Error::new(
+            io::ErrorKind::NotFound,
+            format!("could not find template '{}'", template),
+        )
+        .with_help(help));
+    }
+
+    let crate_name = name.replace('-', "_");
+    let substitute = |contents: &str| {
+        contents
+            .replace("{{name}}", name)
+            .replace("{{crate_name}}", &crate_name)
+    };
+
+    if template_dir.join("src").is_dir() {
+        fs::remove_dir_all(playground.join("src"))?;
+    }
+
+    let skip: Vec<_> = IGNORED.iter().chain(&[FRAGMENT]).copied().collect();
+    helpers::copy_dir(&template_dir, playground, &skip, Some(&substitute))?;
+
+    if let Ok(fragment) = fs::read_to_string(template_dir.join(FRAGMENT)) {
+        manifest.merge(&substitute(&fragment))?;
+    }
+
+    Ok(())
+}
+
+fn validate_name(template: &str) -> error::Result<()> {
+    if template.is_empty()
+        || template == "."
+        || template == ".."
+        || template.contains(&['/', '\\'][..])
+    {
+        return Err(error::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid template name", template),
+        )
+        .with_help("template names cannot be empty or contain path separators"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,45 @@
+use crate::error;
+use std::path::Path;
+use std::{fs, io};
+use toml_edit::{value, Document};
+
+const TOOLCHAIN_FILE: &str = "rust-toolchain.toml";
+/// The older toolchain file, which only contains the channel.
+const LEGACY_TOOLCHAIN_FILE: &str = "rust-toolchain";
+
+/// Pins the playground to the given toolchain by writing a `rust-toolchain.toml` to it.
+pub fn write(playground: &Path, channel: &str) -> error::Result<()> {
+    if channel.is_empty() || channel.contains(char::is_whitespace) {
+        return Err(error::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid toolchain", channel),
+        )
+        .with_help(
+            "the toolchain must be a channel like 'stable', 'nightly', '1.56.0' or \
+             'nightly-2021-10-21'",
+        ));
+    }
+
+    let mut doc = Document::new();
+    doc["toolchain"] = toml_edit::table();
+    doc["toolchain"]["channel"] = value(channel);
+
+    fs::write(playground.join(TOOLCHAIN_FILE), doc.to_string())?;
+
+    Ok(())
+}
+
+/// Reads the toolchain the playground is pinned to, if any.
+pub fn read(playground: &Path) -> Option<String> {
+    if let Ok(contents) = fs::read_to_string(playground.join(TOOLCHAIN_FILE)) {
+        let doc = contents.parse::<Document>().ok()?;
+        return doc
+            .get("toolchain")?
+            .get("channel")?
+            .as_str()
+            .map(String::from);
+    }
+
+    let contents = fs::read_to_string(playground.join(LEGACY_TOOLCHAIN_FILE)).ok()?;
+    Some(contents.trim().to_owned()).filter(|channel| !channel.is_empty())
+}
@@ -2,7 +2,9 @@
 //
 // It has been copied here so cargo-playground can be used without cargo-watch needing to be
 // installed
-use std::{path::MAIN_SEPARATOR, time::Duration};
+use std::{env, path::MAIN_SEPARATOR, time::Duration};
+
+use crate::{error, metadata::METADATA_FILE, open::OPEN_LOCK};
 
 use crossterm::style::Stylize;
 use watchexec::{
@@ -60,7 +62,10 @@ impl<'a> CwHandler<'a> {
     }
 }
 
-pub fn watch(project_id: &str) {
+pub fn watch(project_id: &str) -> error::Result<()> {
+    // The playground is run through the internal `run` command, which records its output
+    let run_cmd = format!("\"{}\" run", env::current_exe()?.display());
+
     let ignores = vec![
         // Mac
         format!("*{}.DS_Store", MAIN_SEPARATOR),
@@ -82,6 +87,9 @@ pub fn watch(project_id: &str) {
         format!("*{s}*.db-journal{s}**", s = MAIN_SEPARATOR),
         // Rust
         format!("*{s}target{s}**", s = MAIN_SEPARATOR),
+        // Playground metadata, which is updated by the runs themselves
+        format!("*{}{}", MAIN_SEPARATOR, METADATA_FILE),
+        format!("*{}{}", MAIN_SEPARATOR, OPEN_LOCK),
     ];
 
     let args = ConfigBuilder::default()
@@ -93,10 +101,12 @@ pub fn watch(project_id: &str) {
         .debounce(Duration::from_millis(500))
         .paths(vec![".".into()])
         .ignores(ignores)
-        .cmd(vec!["cargo run -q".into()])
+        .cmd(vec![run_cmd])
         .build()
         .unwrap();
 
     let handler = CwHandler::new(args, project_id).expect("Failed to create CwHandler");
     watchexec::watch(&handler).expect("Failed to watch source files");
+
+    Ok(())
 }